@@ -0,0 +1,73 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use json_canon::{
+    compare::{compare, compare_datasets, Change},
+    to_string,
+};
+use serde_json::Value;
+
+use crate::{create_output, open_input};
+
+/// Compare two JSON documents, or two JSON Lines datasets, after canonicalization.
+///
+/// Prints a line per change, as `+ PATH VALUE` for an added value, `- PATH VALUE` for a removed
+/// value, or `~ PATH FROM TO` for a changed value, where paths are JSON Pointers and every field
+/// is canonical JSON. When comparing datasets, the canonical key of the record comes before the
+/// path. Exits with 1 if there are any changes.
+#[derive(clap::Args)]
+pub struct Args {
+    /// The first file, or `-` for stdin.
+    a: PathBuf,
+
+    /// The second file, or `-` for stdin.
+    b: PathBuf,
+
+    /// Compare JSON Lines datasets, matching records by the value at this JSON Pointer.
+    #[clap(long, value_name = "POINTER")]
+    key: Option<String>,
+}
+
+pub fn run(args: Args) -> io::Result<i32> {
+    let mut output = create_output(None)?;
+    let mut changed = false;
+    match &args.key {
+        Some(key) => {
+            let a = open_input(Some(&args.a))?;
+            let b = open_input(Some(&args.b))?;
+            for record in compare_datasets(a, b, key)? {
+                write_change(&mut output, Some(&record.key), &record.change)?;
+                changed = true;
+            }
+        }
+        None => {
+            let a: Value = serde_json::from_reader(open_input(Some(&args.a))?)?;
+            let b: Value = serde_json::from_reader(open_input(Some(&args.b))?)?;
+            for change in compare(&a, &b) {
+                write_change(&mut output, None, &change)?;
+                changed = true;
+            }
+        }
+    }
+    output.flush()?;
+    Ok(if changed { 1 } else { 0 })
+}
+
+fn write_change(output: &mut dyn Write, key: Option<&str>, change: &Change) -> io::Result<()> {
+    let (op, values) = match change {
+        Change::Added { value, .. } => ("+", vec![value]),
+        Change::Removed { value, .. } => ("-", vec![value]),
+        Change::Changed { from, to, .. } => ("~", vec![from, to]),
+    };
+    write!(output, "{}", op)?;
+    if let Some(key) = key {
+        write!(output, " {}", key)?;
+    }
+    write!(output, " {}", to_string(change.path())?)?;
+    for value in values {
+        write!(output, " {}", to_string(value)?)?;
+    }
+    writeln!(output)
+}
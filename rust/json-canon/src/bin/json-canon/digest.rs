@@ -0,0 +1,175 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::ArgEnum;
+use json_canon::to_writer;
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::open_input;
+
+/// A hash algorithm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+pub enum Algorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    /// The code of the algorithm in the multihash table.
+    fn multihash_code(self) -> u8 {
+        match self {
+            Algorithm::Sha256 => 0x12,
+            Algorithm::Sha384 => 0x20,
+            Algorithm::Sha512 => 0x13,
+        }
+    }
+
+    fn len(self) -> usize {
+        match self {
+            Algorithm::Sha256 => 32,
+            Algorithm::Sha384 => 48,
+            Algorithm::Sha512 => 64,
+        }
+    }
+
+    const ALL: [Algorithm; 3] = [Algorithm::Sha256, Algorithm::Sha384, Algorithm::Sha512];
+}
+
+/// How a digest is encoded as text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+pub enum Encoding {
+    /// Lowercase hexadecimal.
+    Hex,
+    /// A multihash, in multibase base58btc.
+    Multibase,
+}
+
+/// The digest of the canonical serialization of the JSON file, or stdin if the path is `-`.
+pub fn digest_file(path: &Path, algorithm: Algorithm) -> io::Result<Vec<u8>> {
+    let value: Value = serde_json::from_reader(open_input(Some(path))?)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+    Ok(match algorithm {
+        Algorithm::Sha256 => digest_value::<Sha256>(&value)?,
+        Algorithm::Sha384 => digest_value::<Sha384>(&value)?,
+        Algorithm::Sha512 => digest_value::<Sha512>(&value)?,
+    })
+}
+
+fn digest_value<D>(value: &Value) -> io::Result<Vec<u8>>
+where
+    D: Digest + Write,
+{
+    let mut hasher = D::new();
+    to_writer(&mut hasher, value)?;
+    Ok(hasher.finalize().to_vec())
+}
+
+pub fn encode(digest: &[u8], algorithm: Algorithm, encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Hex => digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        Encoding::Multibase => {
+            let mut multihash = vec![algorithm.multihash_code(), digest.len() as u8];
+            multihash.extend_from_slice(digest);
+            format!("z{}", base58_encode(&multihash))
+        }
+    }
+}
+
+/// Decode an encoded digest, detecting the algorithm from the multihash code, or else from the
+/// length of the hexadecimal digest.
+pub fn decode(encoded: &str) -> Option<(Algorithm, Vec<u8>)> {
+    if let Some(base58) = encoded.strip_prefix('z') {
+        let multihash = base58_decode(base58)?;
+        let (header, digest) = multihash.split_at(multihash.len().min(2));
+        let algorithm = Algorithm::ALL.iter().copied().find(|algorithm| {
+            header == [algorithm.multihash_code(), algorithm.len() as u8]
+                && digest.len() == algorithm.len()
+        })?;
+        return Some((algorithm, digest.to_vec()));
+    }
+    let algorithm = Algorithm::ALL
+        .iter()
+        .copied()
+        .find(|algorithm| encoded.len() == algorithm.len() * 2)?;
+    if !encoded.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digest = (0..encoded.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&encoded[index..index + 2], 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some((algorithm, digest))
+}
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn base58_encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+    // Little-endian base 58 digits.
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for byte in &bytes[zeros..] {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut encoded = "1".repeat(zeros);
+    encoded.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|digit| BASE58_ALPHABET[*digit as usize] as char),
+    );
+    encoded
+}
+
+fn base58_decode(encoded: &str) -> Option<Vec<u8>> {
+    let zeros = encoded.bytes().take_while(|byte| *byte == b'1').count();
+    // Little-endian bytes.
+    let mut bytes: Vec<u8> = Vec::with_capacity(encoded.len());
+    for c in encoded[zeros..].bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|a| *a == c)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+    let mut decoded = vec![0; zeros];
+    decoded.extend(bytes.iter().rev());
+    Some(decoded)
+}
+
+/// The JSON files in the directory and its subdirectories, sorted by path.
+pub fn json_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<io::Result<_>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            files.extend(json_files(&path)?);
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension == "json")
+        {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
@@ -0,0 +1,179 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use json_canon::to_writer;
+use rayon::prelude::*;
+use serde_json::Value;
+
+use crate::{
+    create_output,
+    parallel::{self, map_input},
+    write_atomic,
+};
+
+// Arguments when there is no subcommand. Not a doc comment, which would replace the description
+// of the whole command.
+#[derive(clap::Args)]
+pub struct Args {
+    /// The input files, or `-` for stdin. Defaults to stdin. When canonicalizing more than one
+    /// file to stdout, each document is followed by a newline.
+    files: Vec<PathBuf>,
+
+    /// Check that the input is canonical, instead of printing it. Exits with 1, and prints the
+    /// position of the first byte that differs, for each input that's not canonical.
+    #[clap(long, conflicts_with = "write")]
+    check: bool,
+
+    /// Rewrite each file in place, atomically, if it's not canonical.
+    #[clap(long)]
+    write: bool,
+
+    #[clap(flatten)]
+    parallel: parallel::Args,
+}
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Print,
+    Check,
+    Write,
+}
+
+/// What happened to a file.
+enum Outcome {
+    /// The file is not valid JSON, or can't be canonicalized.
+    Invalid(io::Error),
+    /// The canonical serialization of the file, to print.
+    Canonical(Vec<u8>),
+    /// The file is not canonical, from the line and column.
+    NotCanonical(usize, usize),
+    /// The file is canonical, or was rewritten.
+    Done,
+}
+
+pub fn run(args: Args) -> io::Result<i32> {
+    let files = if args.files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        args.files
+    };
+    if args.write && files.iter().any(|file| file == Path::new("-")) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Can't write stdin in place.",
+        ));
+    }
+    let mode = if args.check {
+        Mode::Check
+    } else if args.write {
+        Mode::Write
+    } else {
+        Mode::Print
+    };
+
+    let pool = args.parallel.pool()?;
+    let mut stats = args.parallel.stats(&pool, "files");
+    let mut output = create_output(None)?;
+    let separate = files.len() > 1;
+    let mut code = 0;
+    // Files are formatted a batch at a time, so the output stays in order without holding every
+    // file in memory.
+    for batch in files.chunks(pool.current_num_threads() * 4) {
+        let outcomes: Vec<_> = pool.install(|| {
+            batch
+                .par_iter()
+                .map(|file| format_file(file, mode))
+                .collect()
+        });
+        for (file, outcome) in batch.iter().zip(outcomes) {
+            let (len, outcome) = outcome?;
+            stats.add(1, len);
+            match outcome {
+                Outcome::Invalid(error) => {
+                    eprintln!("{}: {}", file.display(), error);
+                    code = 1;
+                }
+                Outcome::Canonical(canonical) => {
+                    output.write_all(&canonical)?;
+                    if separate {
+                        output.write_all(b"\n")?;
+                    }
+                }
+                Outcome::NotCanonical(line, column) => {
+                    println!("{}:{}:{}: not canonical", file.display(), line, column);
+                    code = 1;
+                }
+                Outcome::Done => {}
+            }
+        }
+    }
+    output.flush()?;
+    stats.report();
+    Ok(code)
+}
+
+/// Format the file, returning its length and what happened.
+fn format_file(file: &Path, mode: Mode) -> io::Result<(usize, Outcome)> {
+    let input = map_input(file)?;
+    let len = input.len();
+    let canonical = match canonicalize(&input) {
+        Ok(canonical) => canonical,
+        Err(error) => return Ok((len, Outcome::Invalid(error))),
+    };
+    let outcome = match mode {
+        Mode::Print => Outcome::Canonical(canonical),
+        Mode::Check => match first_difference(&input, &canonical) {
+            Some(position) => {
+                let (line, column) = line_column(&input, position);
+                Outcome::NotCanonical(line, column)
+            }
+            None => Outcome::Done,
+        },
+        Mode::Write => {
+            if *input != *canonical {
+                // Unmap the file before replacing it.
+                drop(input);
+                write_atomic(file, &canonical)?;
+            }
+            Outcome::Done
+        }
+    };
+    Ok((len, outcome))
+}
+
+fn canonicalize(input: &[u8]) -> io::Result<Vec<u8>> {
+    let value: Value = serde_json::from_slice(input)?;
+    let mut canonical = Vec::with_capacity(input.len());
+    to_writer(&mut canonical, &value)?;
+    Ok(canonical)
+}
+
+/// The index of the first byte of the input that differs from the canonical bytes, if any.
+fn first_difference(input: &[u8], canonical: &[u8]) -> Option<usize> {
+    if input == canonical {
+        return None;
+    }
+    let common = input
+        .iter()
+        .zip(canonical)
+        .take_while(|(a, b)| a == b)
+        .count();
+    Some(common)
+}
+
+/// The 1-based line and column (in characters) of the byte index in the input.
+fn line_column(input: &[u8], index: usize) -> (usize, usize) {
+    let before = &input[..index.min(input.len())];
+    let line = before.iter().filter(|byte| **byte == b'\n').count() + 1;
+    let line_start = before
+        .iter()
+        .rposition(|byte| *byte == b'\n')
+        .map_or(0, |newline| newline + 1);
+    let column = String::from_utf8_lossy(&before[line_start..])
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
@@ -0,0 +1,18 @@
+use std::io::{self, BufReader, BufWriter};
+
+use json_canon::git_filter::filter_process;
+
+/// Run as a Git long-running filter process, which cleans JSON files to their canonical form,
+/// and smudges them to a pretty form.
+///
+/// Configure with `git config filter.json-canon.process "json-canon git-filter"`, and
+/// `*.json filter=json-canon` in `.gitattributes`.
+#[derive(clap::Args)]
+pub struct Args {}
+
+pub fn run(_args: Args) -> io::Result<i32> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    filter_process(BufReader::new(stdin.lock()), BufWriter::new(stdout.lock()))?;
+    Ok(0)
+}
@@ -0,0 +1,54 @@
+use std::{io, path::PathBuf};
+
+use crate::digest::{digest_file, encode, json_files, Algorithm, Encoding};
+
+/// Print the digest of the canonical serialization of JSON files.
+///
+/// Prints a line per file, as `DIGEST  PATH`.
+#[derive(clap::Args)]
+pub struct Args {
+    /// The input files, or `-` for stdin. Defaults to stdin.
+    paths: Vec<PathBuf>,
+
+    /// Hash the JSON files (with a `.json` extension) in directories, recursively.
+    #[clap(short, long)]
+    recursive: bool,
+
+    /// The hash algorithm.
+    #[clap(short, long, arg_enum, default_value = "sha256")]
+    algorithm: Algorithm,
+
+    /// How to encode the digest.
+    #[clap(short, long, arg_enum, default_value = "hex")]
+    encoding: Encoding,
+}
+
+pub fn run(args: Args) -> io::Result<i32> {
+    let mut files = Vec::new();
+    for path in args.paths {
+        if args.recursive && path.is_dir() {
+            files.extend(json_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    if files.is_empty() {
+        files.push(PathBuf::from("-"));
+    }
+
+    let mut code = 0;
+    for file in &files {
+        match digest_file(file, args.algorithm) {
+            Ok(digest) => println!(
+                "{}  {}",
+                encode(&digest, args.algorithm, args.encoding),
+                file.display()
+            ),
+            Err(error) => {
+                eprintln!("{}: {}", file.display(), error);
+                code = 1;
+            }
+        }
+    }
+    Ok(code)
+}
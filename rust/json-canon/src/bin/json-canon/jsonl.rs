@@ -0,0 +1,211 @@
+use std::{
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::ArgEnum;
+use json_canon::{
+    dataset::{DatasetOptions, DatasetWriter, Dedupe},
+    stream::{Framing, OnError, RecordError, Records},
+    to_vec,
+};
+use rayon::{prelude::*, ThreadPool};
+
+use crate::{
+    create_output, open_input,
+    parallel::{self, map_input, Stats},
+};
+
+/// Canonicalize each line of a JSON Lines dataset, optionally sorting and removing duplicates.
+#[derive(clap::Args)]
+pub struct Args {
+    /// The input file, or `-` for stdin.
+    input: Option<PathBuf>,
+
+    /// The output file, or `-` for stdout.
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+
+    /// Sort lines by their canonical bytes.
+    #[clap(long)]
+    sort: bool,
+
+    /// Remove duplicate lines, comparing their canonical bytes or hashes.
+    #[clap(long, arg_enum, value_name = "BY")]
+    dedupe: Option<DedupeBy>,
+
+    /// Stop at the first line that is not valid, instead of skipping it.
+    #[clap(long)]
+    abort_on_error: bool,
+
+    /// The number of bytes to sort in memory, before spilling to temporary files.
+    #[clap(long, value_name = "BYTES", default_value_t = DatasetOptions::default().max_memory)]
+    max_memory: usize,
+
+    /// The directory for temporary files.
+    #[clap(long, value_name = "DIR")]
+    temp_dir: Option<PathBuf>,
+
+    /// The number of bytes of lines to canonicalize in each parallel task.
+    #[clap(long, value_name = "BYTES", default_value_t = 1024 * 1024)]
+    chunk_size: usize,
+
+    #[clap(flatten)]
+    parallel: parallel::Args,
+}
+
+#[derive(Clone, Copy, ArgEnum)]
+enum DedupeBy {
+    Bytes,
+    Hash,
+}
+
+pub fn run(args: Args) -> io::Result<i32> {
+    let options = DatasetOptions {
+        sort: args.sort,
+        dedupe: args.dedupe.map(|dedupe| match dedupe {
+            DedupeBy::Bytes => Dedupe::Bytes,
+            DedupeBy::Hash => Dedupe::Hash,
+        }),
+        on_error: if args.abort_on_error {
+            OnError::Abort
+        } else {
+            OnError::Skip
+        },
+        max_memory: args.max_memory,
+        temp_dir: args.temp_dir,
+    };
+    let pool = args.parallel.pool()?;
+    let mut stats = args.parallel.stats(&pool, "records");
+    let output = create_output(args.output.as_ref())?;
+    let mut dataset = Dataset {
+        writer: DatasetWriter::new(output, options),
+        records: 0,
+    };
+
+    // Lines are canonicalized in chunks in parallel, a batch of chunks at a time, then written in
+    // order.
+    let batch = pool.current_num_threads() * 4;
+    match args.input.as_deref() {
+        Some(path) if path != Path::new("-") => {
+            let input = map_input(path)?;
+            let chunks = split_chunks(&input, args.chunk_size);
+            for chunks in chunks.chunks(batch) {
+                dataset.write_batch(&pool, chunks, &mut stats)?;
+            }
+        }
+        _ => {
+            let mut input = open_input(None)?;
+            loop {
+                let mut chunks = Vec::with_capacity(batch);
+                while chunks.len() < batch {
+                    let chunk = read_chunk(&mut input, args.chunk_size)?;
+                    if chunk.is_empty() {
+                        break;
+                    }
+                    chunks.push(chunk);
+                }
+                if chunks.is_empty() {
+                    break;
+                }
+                let chunks: Vec<&[u8]> = chunks.iter().map(Vec::as_slice).collect();
+                dataset.write_batch(&pool, &chunks, &mut stats)?;
+            }
+        }
+    }
+
+    let report = dataset.writer.finish()?;
+    for error in &report.errors {
+        eprintln!("json-canon: {}", error);
+    }
+    stats.report();
+    Ok(if report.errors.is_empty() { 0 } else { 1 })
+}
+
+struct Dataset<W> {
+    writer: DatasetWriter<W>,
+    /// The number of records read so far, to offset the indexes of records in each chunk.
+    records: usize,
+}
+
+impl<W> Dataset<W>
+where
+    W: Write,
+{
+    fn write_batch(
+        &mut self,
+        pool: &ThreadPool,
+        chunks: &[&[u8]],
+        stats: &mut Stats,
+    ) -> io::Result<()> {
+        let canonicalized: Vec<_> = pool.install(|| {
+            chunks
+                .par_iter()
+                .map(|chunk| canonicalize_chunk(chunk))
+                .collect()
+        });
+        for (chunk, lines) in chunks.iter().zip(canonicalized) {
+            let lines = lines?;
+            stats.add(lines.len(), chunk.len());
+            let records = self.records;
+            self.records += lines.len();
+            for line in lines {
+                match line {
+                    Ok(line) => self.writer.push(line)?,
+                    Err(mut error) => {
+                        error.index += records;
+                        self.writer.push_error(error)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Canonicalize each line of the chunk, where record indexes count from the start of the chunk.
+fn canonicalize_chunk(chunk: &[u8]) -> io::Result<Vec<Result<Vec<u8>, RecordError>>> {
+    Records::new(chunk, Framing::Lines)
+        .enumerate()
+        .map(|(index, record)| {
+            Ok(record?.and_then(|value| {
+                to_vec(&value).map_err(|error| RecordError {
+                    index,
+                    error: error.into(),
+                })
+            }))
+        })
+        .collect()
+}
+
+/// Split the input into chunks of whole lines, of at least the size, except the last chunk.
+fn split_chunks(input: &[u8], size: usize) -> Vec<&[u8]> {
+    let mut chunks = Vec::with_capacity(input.len() / size.max(1) + 1);
+    let mut rest = input;
+    while !rest.is_empty() {
+        let end = match rest.get(size..) {
+            Some(after) => after
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .map_or(rest.len(), |newline| size + newline + 1),
+            None => rest.len(),
+        };
+        let (chunk, after) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = after;
+    }
+    chunks
+}
+
+/// Read a chunk of whole lines, of at least the size, unless at the end of the input.
+fn read_chunk<R>(reader: &mut R, size: usize) -> io::Result<Vec<u8>>
+where
+    R: BufRead,
+{
+    let mut chunk = Vec::with_capacity(size);
+    reader.take(size as u64).read_to_end(&mut chunk)?;
+    if chunk.last().map_or(false, |byte| *byte != b'\n') {
+        reader.read_until(b'\n', &mut chunk)?;
+    }
+    Ok(chunk)
+}
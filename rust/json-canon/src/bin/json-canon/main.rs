@@ -0,0 +1,118 @@
+//! The `json-canon` command-line interface.
+//!
+//! Exits with 0 on success, 1 if the command found a problem with its input (such as records
+//! that are not valid), or 2 if the command failed. `verify --strict` exits with 3 if the payload
+//! is not canonical.
+
+mod diff;
+mod digest;
+mod format;
+mod git_filter;
+mod hash;
+mod jsonl;
+mod manifest;
+mod parallel;
+mod sign;
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[clap(
+    name = "json-canon",
+    version,
+    about,
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    #[clap(flatten)]
+    format: format::Args,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Diff(diff::Args),
+    GitFilter(git_filter::Args),
+    Hash(hash::Args),
+    Jsonl(jsonl::Args),
+    Manifest(manifest::Args),
+    Sign(sign::SignArgs),
+    Verify(sign::VerifyArgs),
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        None => format::run(cli.format),
+        Some(Command::Diff(args)) => diff::run(args),
+        Some(Command::GitFilter(args)) => git_filter::run(args),
+        Some(Command::Hash(args)) => hash::run(args),
+        Some(Command::Jsonl(args)) => jsonl::run(args),
+        Some(Command::Manifest(args)) => manifest::run(args),
+        Some(Command::Sign(args)) => sign::sign(args),
+        Some(Command::Verify(args)) => sign::verify(args),
+    };
+    match result {
+        Ok(code) => process::exit(code),
+        Err(error) => {
+            eprintln!("json-canon: {}", error);
+            process::exit(2);
+        }
+    }
+}
+
+/// Open the input file, or stdin if the path is absent or `-`.
+fn open_input(path: Option<&Path>) -> io::Result<Box<dyn BufRead>> {
+    match path {
+        Some(path) if path != Path::new("-") => Ok(Box::new(BufReader::new(File::open(path)?))),
+        _ => Ok(Box::new(BufReader::new(io::stdin()))),
+    }
+}
+
+/// Create the output file, or stdout if the path is absent or `-`.
+fn create_output(path: Option<&PathBuf>) -> io::Result<Box<dyn Write>> {
+    match path {
+        Some(path) if path != Path::new("-") => Ok(Box::new(BufWriter::new(File::create(path)?))),
+        _ => Ok(Box::new(BufWriter::new(io::stdout()))),
+    }
+}
+
+/// Replace the contents of the file atomically: write a temporary file next to it, then rename
+/// the temporary file over it.
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if dir != Path::new("") => dir,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut random = [0; 8];
+    getrandom::getrandom(&mut random)?;
+    let suffix: String = random.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let temp = dir.join(format!(".{}.{}.tmp", name, suffix));
+
+    let result = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        file.write_all(contents)?;
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
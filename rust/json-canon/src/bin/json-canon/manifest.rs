@@ -0,0 +1,141 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use json_canon::to_writer;
+use serde_json::{Map, Value};
+
+use crate::{
+    create_output,
+    digest::{decode, digest_file, encode, json_files, Algorithm, Encoding},
+    write_atomic,
+};
+
+/// Write a manifest of the JSON files in a directory, or verify a directory against a manifest.
+///
+/// A manifest is a canonical JSON object, from the path of each JSON file (with a `.json`
+/// extension) relative to the directory, to the digest of its canonical serialization.
+#[derive(clap::Args)]
+pub struct Args {
+    /// The directory.
+    dir: PathBuf,
+
+    /// The output file, or `-` for stdout.
+    #[clap(short, long, conflicts_with = "verify")]
+    output: Option<PathBuf>,
+
+    /// Verify the directory against this manifest, instead of writing a manifest. Exits with 1,
+    /// and prints each path that is changed, missing, or not in the manifest, if the directory
+    /// doesn't match.
+    #[clap(long, value_name = "MANIFEST")]
+    verify: Option<PathBuf>,
+
+    /// The hash algorithm. When verifying, the algorithm is detected from each digest.
+    #[clap(short, long, arg_enum, default_value = "sha256")]
+    algorithm: Algorithm,
+
+    /// How to encode the digests. When verifying, the encoding is detected from each digest.
+    #[clap(short, long, arg_enum, default_value = "hex")]
+    encoding: Encoding,
+}
+
+pub fn run(args: Args) -> io::Result<i32> {
+    match &args.verify {
+        Some(manifest) => verify(&args.dir, manifest),
+        None => write(&args),
+    }
+}
+
+fn write(args: &Args) -> io::Result<i32> {
+    let exclude = args.output.as_deref();
+    let mut manifest = Map::new();
+    for (relative, file) in files(&args.dir, exclude)? {
+        let digest = digest_file(&file, args.algorithm).map_err(|error| in_file(&file, error))?;
+        let digest = encode(&digest, args.algorithm, args.encoding);
+        manifest.insert(relative, Value::String(digest));
+    }
+
+    let mut data = Vec::new();
+    to_writer(&mut data, &manifest)?;
+    match &args.output {
+        Some(output) if output != Path::new("-") => write_atomic(output, &data)?,
+        _ => {
+            let mut output = create_output(None)?;
+            output.write_all(&data)?;
+            output.flush()?;
+        }
+    }
+    Ok(0)
+}
+
+fn verify(dir: &Path, manifest_path: &Path) -> io::Result<i32> {
+    let manifest: Map<String, Value> = serde_json::from_slice(&fs::read(manifest_path)?)
+        .map_err(|error| in_file(manifest_path, error.into()))?;
+
+    let mut code = 0;
+    let mut problem = |relative: &str, problem: &str| {
+        println!("{}: {}", relative, problem);
+        code = 1;
+    };
+    let files = files(dir, Some(manifest_path))?;
+    for (relative, file) in &files {
+        let expected = match manifest.get(relative) {
+            Some(expected) => expected,
+            None => {
+                problem(relative, "not in manifest");
+                continue;
+            }
+        };
+        let (algorithm, expected) = match expected.as_str().and_then(decode) {
+            Some(decoded) => decoded,
+            None => {
+                problem(relative, "invalid digest in manifest");
+                continue;
+            }
+        };
+        match digest_file(file, algorithm) {
+            Ok(actual) if actual == expected => {}
+            Ok(_) => problem(relative, "changed"),
+            Err(error) => problem(relative, &error.to_string()),
+        }
+    }
+    for relative in manifest.keys() {
+        if !files.iter().any(|(other, _)| other == relative) {
+            problem(relative, "missing");
+        }
+    }
+    Ok(code)
+}
+
+/// The JSON files in the directory, with their paths relative to the directory, excluding the
+/// manifest itself.
+fn files(dir: &Path, exclude: Option<&Path>) -> io::Result<Vec<(String, PathBuf)>> {
+    let exclude = exclude.and_then(|exclude| fs::canonicalize(exclude).ok());
+    let mut files = Vec::new();
+    for file in json_files(dir)? {
+        if exclude.is_some() && fs::canonicalize(&file).ok() == exclude {
+            continue;
+        }
+        let relative = file
+            .strip_prefix(dir)
+            .unwrap_or(&file)
+            .iter()
+            .map(|component| component.to_str())
+            .collect::<Option<Vec<&str>>>()
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Path is not valid UTF-8: {}", file.display()),
+                )
+            })?
+            .join("/");
+        files.push((relative, file));
+    }
+    Ok(files)
+}
+
+fn in_file(path: &Path, error: Error) -> Error {
+    Error::new(error.kind(), format!("{}: {}", path.display(), error))
+}
@@ -0,0 +1,137 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    ops::Deref,
+    path::Path,
+    time::Instant,
+};
+
+use memmap2::Mmap;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+/// Options for processing in parallel.
+#[derive(clap::Args)]
+pub struct Args {
+    /// The number of worker threads. Defaults to the number of CPUs.
+    #[clap(short, long, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Print throughput statistics to stderr.
+    #[clap(long)]
+    stats: bool,
+}
+
+impl Args {
+    /// Build the pool of worker threads.
+    pub fn pool(&self) -> io::Result<ThreadPool> {
+        ThreadPoolBuilder::new()
+            .num_threads(self.jobs.unwrap_or(0))
+            .build()
+            .map_err(|error| io::Error::new(io::ErrorKind::Other, error))
+    }
+
+    /// Start timing, counting items in the unit.
+    pub fn stats(&self, pool: &ThreadPool, unit: &'static str) -> Stats {
+        Stats {
+            enabled: self.stats,
+            threads: pool.current_num_threads(),
+            unit,
+            start: Instant::now(),
+            items: 0,
+            bytes: 0,
+        }
+    }
+}
+
+/// Throughput statistics, in the same units as the benchmarks.
+pub struct Stats {
+    enabled: bool,
+    threads: usize,
+    unit: &'static str,
+    start: Instant,
+    items: usize,
+    bytes: usize,
+}
+
+impl Stats {
+    pub fn add(&mut self, items: usize, bytes: usize) {
+        self.items += items;
+        self.bytes += bytes;
+    }
+
+    /// Print the statistics to stderr, if enabled.
+    pub fn report(&self) {
+        if !self.enabled {
+            return;
+        }
+        let seconds = self.start.elapsed().as_secs_f64();
+        let mebibytes = self.bytes as f64 / (1024.0 * 1024.0);
+        let (rate, prefix) = scale(self.items as f64 / seconds);
+        eprintln!(
+            "json-canon: {} {}, {:.1} MiB in {:.3} s on {} {}: {:.3} {}elem/s, {:.1} MiB/s",
+            self.items,
+            self.unit,
+            mebibytes,
+            seconds,
+            self.threads,
+            if self.threads == 1 {
+                "thread"
+            } else {
+                "threads"
+            },
+            rate,
+            prefix,
+            mebibytes / seconds,
+        );
+    }
+}
+
+/// Scale a rate to a metric prefix, as `criterion` does.
+fn scale(rate: f64) -> (f64, &'static str) {
+    if rate < 1e3 {
+        (rate, "")
+    } else if rate < 1e6 {
+        (rate / 1e3, "K")
+    } else if rate < 1e9 {
+        (rate / 1e6, "M")
+    } else {
+        (rate / 1e9, "G")
+    }
+}
+
+/// The contents of an input, either mapped into memory or read.
+pub enum Input {
+    Mapped(Mmap),
+    Read(Vec<u8>),
+}
+
+impl Deref for Input {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Input::Mapped(map) => map,
+            Input::Read(data) => data,
+        }
+    }
+}
+
+/// Map the file into memory, or read it if it can't be mapped (such as a pipe), or read stdin if
+/// the path is `-`.
+pub fn map_input(path: &Path) -> io::Result<Input> {
+    let mut data = Vec::new();
+    if path == Path::new("-") {
+        io::stdin().read_to_end(&mut data)?;
+        return Ok(Input::Read(data));
+    }
+    let mut file = File::open(path)?;
+    if file.metadata()?.is_file() {
+        // SAFETY: The map is only read. If another process changes the file while it's mapped,
+        // the output may be wrong, the same as if the file changed while being read.
+        if let Ok(map) = unsafe { Mmap::map(&file) } {
+            return Ok(Input::Mapped(map));
+        }
+    }
+    file.read_to_end(&mut data)?;
+    Ok(Input::Read(data))
+}
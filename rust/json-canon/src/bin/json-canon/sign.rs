@@ -0,0 +1,225 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ed25519_dalek::{
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    Signature, Signer, SigningKey, VerifyingKey,
+};
+use json_canon::{
+    patch::{Operation, Patch},
+    CanonicalFormatter,
+};
+use serde::Serialize;
+use serde_json::{Serializer, Value};
+
+use crate::{create_output, open_input};
+
+/// Exit code when the signature doesn't match.
+const MISMATCH: i32 = 1;
+/// Exit code when the payload is not canonical, with `--strict`.
+const NOT_CANONICAL: i32 = 3;
+
+/// Sign the canonical serialization of a JSON payload with an Ed25519 key.
+///
+/// By default, the signature is embedded in the payload at a JSON Pointer, and the signed payload
+/// is printed. The signature covers the canonical serialization of the payload without the
+/// signature. With `--detached`, only the signature is printed.
+///
+/// Signatures are encoded as base64url, without padding.
+#[derive(clap::Args)]
+pub struct SignArgs {
+    /// The payload file, or `-` for stdin.
+    input: Option<PathBuf>,
+
+    /// The Ed25519 private key, as a PKCS#8 PEM file or a JWK file.
+    #[clap(short, long, value_name = "FILE")]
+    key: PathBuf,
+
+    /// Print a detached signature, instead of embedding the signature in the payload.
+    #[clap(long)]
+    detached: bool,
+
+    /// The JSON Pointer at which to embed the signature.
+    #[clap(long, value_name = "POINTER", default_value = "/signature")]
+    pointer: String,
+
+    /// The output file, or `-` for stdout.
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Verify the Ed25519 signature of the canonical serialization of a JSON payload.
+///
+/// By default, the signature is embedded in the payload at a JSON Pointer. With `--signature`,
+/// the signature is detached in a file.
+///
+/// Exits with 0 if the signature matches, 1 if it doesn't, or (with `--strict`) 3 if the payload
+/// is not canonical.
+#[derive(clap::Args)]
+pub struct VerifyArgs {
+    /// The payload file, or `-` for stdin.
+    input: Option<PathBuf>,
+
+    /// The Ed25519 public (or private) key, as a PEM file or a JWK file.
+    #[clap(short, long, value_name = "FILE")]
+    key: PathBuf,
+
+    /// A file with the detached signature.
+    #[clap(short, long, value_name = "FILE")]
+    signature: Option<PathBuf>,
+
+    /// The JSON Pointer of the embedded signature.
+    #[clap(long, value_name = "POINTER", default_value = "/signature")]
+    pointer: String,
+
+    /// Fail if the payload, as given, is not canonical. One trailing newline is allowed.
+    #[clap(long)]
+    strict: bool,
+}
+
+pub fn sign(args: SignArgs) -> io::Result<i32> {
+    let key = read_signing_key(&args.key)?;
+    let (_, mut value) = read_payload(args.input.as_deref())?;
+    if !args.detached {
+        remove_signature(&mut value, &args.pointer)?;
+    }
+    let signature = key.sign(&canonicalize(&value)?);
+    let signature = URL_SAFE_NO_PAD.encode(signature.to_bytes());
+
+    let mut output = create_output(args.output.as_ref())?;
+    if args.detached {
+        writeln!(output, "{}", signature)?;
+    } else {
+        Patch(vec![Operation::Add {
+            path: args.pointer,
+            value: Value::String(signature),
+        }])
+        .apply(&mut value)?;
+        output.write_all(&canonicalize(&value)?)?;
+    }
+    output.flush()?;
+    Ok(0)
+}
+
+pub fn verify(args: VerifyArgs) -> io::Result<i32> {
+    let key = read_verifying_key(&args.key)?;
+    let (input, mut value) = read_payload(args.input.as_deref())?;
+    // Editors, and `echo`, end a file with a newline.
+    let input = input.strip_suffix(b"\n").unwrap_or(&input);
+    if input != canonicalize(&value)? {
+        if args.strict {
+            eprintln!("json-canon: Payload is not canonical.");
+            return Ok(NOT_CANONICAL);
+        }
+        eprintln!("json-canon: Payload is not canonical, verifying its canonical form.");
+    }
+
+    let signature = match &args.signature {
+        Some(path) => fs::read_to_string(path)?,
+        None => match remove_signature(&mut value, &args.pointer)? {
+            Some(Value::String(signature)) => signature,
+            Some(_) => return Err(invalid("Signature is not a string.")),
+            None => {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("No signature at {:?}.", args.pointer),
+                ))
+            }
+        },
+    };
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature.trim())
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or_else(|| invalid("Signature is not a base64url Ed25519 signature."))?;
+
+    // Strict verification rejects small-order keys and malleable signatures.
+    if key
+        .verify_strict(&canonicalize(&value)?, &signature)
+        .is_ok()
+    {
+        println!("Signature matches.");
+        Ok(0)
+    } else {
+        println!("Signature doesn't match.");
+        Ok(MISMATCH)
+    }
+}
+
+/// Serialize the value with the canonical formatter.
+fn canonicalize(value: &Value) -> io::Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(128);
+    value.serialize(&mut Serializer::with_formatter(
+        &mut data,
+        CanonicalFormatter::new(),
+    ))?;
+    Ok(data)
+}
+
+fn read_payload(path: Option<&Path>) -> io::Result<(Vec<u8>, Value)> {
+    let mut input = Vec::new();
+    open_input(path)?.read_to_end(&mut input)?;
+    let value = serde_json::from_slice(&input)?;
+    Ok((input, value))
+}
+
+/// Remove the embedded signature at the pointer, if any, returning it.
+fn remove_signature(value: &mut Value, pointer: &str) -> io::Result<Option<Value>> {
+    let signature = match value.pointer(pointer) {
+        Some(signature) => signature.clone(),
+        None => return Ok(None),
+    };
+    Patch(vec![Operation::Remove {
+        path: pointer.to_owned(),
+    }])
+    .apply(value)?;
+    Ok(Some(signature))
+}
+
+fn read_signing_key(path: &Path) -> io::Result<SigningKey> {
+    let key = fs::read_to_string(path)?;
+    if key.trim_start().starts_with('{') {
+        let jwk = read_jwk(&key)?;
+        let d = jwk_bytes(&jwk, "d")?;
+        return Ok(SigningKey::from_bytes(&d));
+    }
+    SigningKey::from_pkcs8_pem(&key).map_err(|error| invalid(&error.to_string()))
+}
+
+fn read_verifying_key(path: &Path) -> io::Result<VerifyingKey> {
+    let key = fs::read_to_string(path)?;
+    if key.trim_start().starts_with('{') {
+        let jwk = read_jwk(&key)?;
+        let x = jwk_bytes(&jwk, "x")?;
+        return VerifyingKey::from_bytes(&x).map_err(|error| invalid(&error.to_string()));
+    }
+    if key.contains("PRIVATE KEY") {
+        return Ok(read_signing_key(path)?.verifying_key());
+    }
+    VerifyingKey::from_public_key_pem(&key).map_err(|error| invalid(&error.to_string()))
+}
+
+/// Parse an Ed25519 JSON Web Key ([RFC 8037](https://tools.ietf.org/html/rfc8037)).
+fn read_jwk(key: &str) -> io::Result<Value> {
+    let jwk: Value = serde_json::from_str(key)?;
+    if jwk["kty"] != "OKP" || jwk["crv"] != "Ed25519" {
+        return Err(invalid("JWK is not an Ed25519 key."));
+    }
+    Ok(jwk)
+}
+
+fn jwk_bytes(jwk: &Value, name: &str) -> io::Result<[u8; 32]> {
+    jwk[name]
+        .as_str()
+        .and_then(|encoded| URL_SAFE_NO_PAD.decode(encoded).ok())
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| invalid(&format!("JWK has no valid {:?} parameter.", name)))
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_owned())
+}
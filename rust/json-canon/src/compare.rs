@@ -0,0 +1,185 @@
+//! Semantic comparison of JSON documents and JSON Lines datasets.
+//!
+//! Unlike a text diff, [`compare`] reports which paths were added, removed, or changed, where
+//! values are compared by their canonical serialization, so `1.0` and `1` are not a change.
+//! Object members are compared by key, and array elements by index.
+//!
+//! [`compare_datasets`] compares two JSON Lines datasets, where records are matched by the value
+//! at a JSON Pointer in each record.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Error, ErrorKind},
+};
+
+use serde_json::Value;
+
+use crate::{
+    object::cmp_keys,
+    pointer,
+    stream::{Framing, Records},
+    to_string,
+    value::{canonical_eq, zip_members},
+};
+
+/// A difference between two values, at a JSON Pointer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// A value only in the second value.
+    Added { path: String, value: Value },
+    /// A value only in the first value.
+    Removed { path: String, value: Value },
+    /// A value that is different in each value.
+    Changed {
+        path: String,
+        from: Value,
+        to: Value,
+    },
+}
+
+impl Change {
+    /// The JSON Pointer to the value.
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// A difference between the records with the same key in two datasets.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordChange {
+    /// The canonical serialization of the key.
+    pub key: String,
+    /// The difference. For a record only in one dataset, the path is the root `""`.
+    pub change: Change,
+}
+
+/// Compare two values, returning the changes from `a` to `b`, in canonical order.
+pub fn compare(a: &Value, b: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    compare_value(a, b, "", &mut changes);
+    changes
+}
+
+fn compare_value(a: &Value, b: &Value, path: &str, changes: &mut Vec<Change>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, a, b) in zip_members(a, b) {
+                let path = pointer::join(path, key);
+                match (a, b) {
+                    (Some(a), Some(b)) => compare_value(a, b, &path, changes),
+                    (Some(a), None) => changes.push(Change::Removed {
+                        path,
+                        value: a.clone(),
+                    }),
+                    (None, Some(b)) => changes.push(Change::Added {
+                        path,
+                        value: b.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for index in 0..a.len().max(b.len()) {
+                let path = format!("{}/{}", path, index);
+                match (a.get(index), b.get(index)) {
+                    (Some(a), Some(b)) => compare_value(a, b, &path, changes),
+                    (Some(a), None) => changes.push(Change::Removed {
+                        path,
+                        value: a.clone(),
+                    }),
+                    (None, Some(b)) => changes.push(Change::Added {
+                        path,
+                        value: b.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (a, b) => {
+            if !canonical_eq(a, b) {
+                changes.push(Change::Changed {
+                    path: path.to_owned(),
+                    from: a.clone(),
+                    to: b.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Compare two JSON Lines datasets, matching records by the value at the JSON Pointer `key`.
+///
+/// Returns the changes in order of the canonical serialization of the keys.
+///
+/// # Errors
+///
+/// Errors if either IO stream errors, if a record is not valid, if a record has no value at the
+/// key pointer, or if two records in the same dataset have the same key.
+pub fn compare_datasets<A, B>(a: A, b: B, key: &str) -> io::Result<Vec<RecordChange>>
+where
+    A: BufRead,
+    B: BufRead,
+{
+    pointer::parse(key)?;
+    let a = read_keyed(a, key)?;
+    let mut b = read_keyed(b, key)?;
+
+    let mut changes = Vec::new();
+    for (key, a) in a {
+        match b.remove(&key) {
+            Some(b) => changes.extend(compare(&a, &b).into_iter().map(|change| RecordChange {
+                key: key.clone(),
+                change,
+            })),
+            None => changes.push(RecordChange {
+                key,
+                change: Change::Removed {
+                    path: String::new(),
+                    value: a,
+                },
+            }),
+        }
+    }
+    for (key, b) in b {
+        changes.push(RecordChange {
+            key,
+            change: Change::Added {
+                path: String::new(),
+                value: b,
+            },
+        });
+    }
+    changes.sort_by(|a, b| cmp_keys(&a.key, &b.key));
+    Ok(changes)
+}
+
+/// Read the records of a dataset, keyed by the canonical serialization of their key.
+fn read_keyed<R>(reader: R, key: &str) -> io::Result<BTreeMap<String, Value>>
+where
+    R: BufRead,
+{
+    let mut records = BTreeMap::new();
+    for (index, record) in Records::new(reader, Framing::Lines).enumerate() {
+        let record = record??;
+        let record_key = record.pointer(key).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Record {} has no value at {:?}.", index, key),
+            )
+        })?;
+        let record_key = to_string(record_key)?;
+        if records.contains_key(&record_key) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Record {} has a duplicate key {}.", index, record_key),
+            ));
+        }
+        records.insert(record_key, record);
+    }
+    Ok(records)
+}
@@ -0,0 +1,220 @@
+//! Cosmos SDK legacy amino JSON sign docs.
+//!
+//! Transactions signed with `SIGN_MODE_LEGACY_AMINO_JSON` sign the bytes of a [`StdSignDoc`]: its
+//! amino JSON, with the keys of every object sorted, as re-encoded by Go's `encoding/json`
+//! (`sdk.MustSortJSON`). That is close to RFC 8785, but not the same:
+//!
+//! - Keys are sorted by their UTF-8 bytes, rather than their UTF-16 code units.
+//! - Strings are escaped as Go escapes them, which is [`Escaping::Html`], except that backspace
+//!   and form feed are written as `\u0008` and `\u000c`, as Go's `encoding/json` did before Go
+//!   1.22 (which writes `\b` and `\f`). Sign docs have been signed with those bytes since amino
+//!   JSON was introduced, so a chain built with Go 1.22 or later doesn't match them.
+//! - Amino writes 64-bit integers as strings, such as `"gas":"200000"`.
+//!
+//! ```rust
+//! use json_canon::cosmos::{AminoMsg, Coin, StdFee, StdSignDoc};
+//! use serde::Serialize;
+//! # fn main() -> std::io::Result<()> {
+//!
+//! #[derive(Serialize)]
+//! struct MsgSend {
+//!     from_address: String,
+//!     to_address: String,
+//!     amount: Vec<Coin>,
+//! }
+//!
+//! let doc = StdSignDoc {
+//!     account_number: 7,
+//!     chain_id: "cosmoshub-4".to_owned(),
+//!     fee: StdFee::new(vec![Coin::new(2000, "uatom")], 200_000),
+//!     memo: String::new(),
+//!     msgs: vec![AminoMsg::new(
+//!         "cosmos-sdk/MsgSend",
+//!         MsgSend {
+//!             from_address: "cosmos1from".to_owned(),
+//!             to_address: "cosmos1to".to_owned(),
+//!             amount: vec![Coin::new(1, "uatom")],
+//!         },
+//!     )],
+//!     sequence: 0,
+//!     timeout_height: 0,
+//! };
+//! assert_eq!(
+//!     doc.sign_bytes()?,
+//!     br#"{"account_number":"7","chain_id":"cosmoshub-4","fee":{"amount":[{"amount":"2000","denom":"uatom"}],"gas":"200000"},"memo":"","msgs":[{"type":"cosmos-sdk/MsgSend","value":{"amount":[{"amount":"1","denom":"uatom"}],"from_address":"cosmos1from","to_address":"cosmos1to"}}],"sequence":"0"}"#
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+use serde::{Serialize, Serializer};
+use serde_json::{Result, Value};
+
+use crate::{escape::Escaping, order::CodePointOrder, CanonicalFormatter};
+
+/// An amount of a denomination.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Coin {
+    pub denom: String,
+    /// The amount, as a decimal integer, which amino writes as a string.
+    pub amount: String,
+}
+
+impl Coin {
+    pub fn new<D>(amount: u128, denom: D) -> Self
+    where
+        D: Into<String>,
+    {
+        Self {
+            denom: denom.into(),
+            amount: amount.to_string(),
+        }
+    }
+}
+
+/// The fee of a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StdFee {
+    pub amount: Vec<Coin>,
+    #[serde(serialize_with = "as_string")]
+    pub gas: u64,
+    /// The address that pays the fee, if not the first signer, or else empty.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub payer: String,
+    /// The address that grants the fee allowance, if any, or else empty.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub granter: String,
+}
+
+impl StdFee {
+    pub fn new(amount: Vec<Coin>, gas: u64) -> Self {
+        Self {
+            amount,
+            gas,
+            payer: String::new(),
+            granter: String::new(),
+        }
+    }
+}
+
+/// A message, as amino JSON: its registered amino name, and its value.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AminoMsg<T> {
+    /// The amino name, such as `cosmos-sdk/MsgSend`.
+    pub r#type: String,
+    pub value: T,
+}
+
+impl<T> AminoMsg<T> {
+    pub fn new<N>(name: N, value: T) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            r#type: name.into(),
+            value,
+        }
+    }
+}
+
+/// The document that is signed with `SIGN_MODE_LEGACY_AMINO_JSON`.
+///
+/// Messages are usually [`AminoMsg`]s, or [`Value`]s of amino JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StdSignDoc<M = Value> {
+    #[serde(serialize_with = "as_string")]
+    pub account_number: u64,
+    pub chain_id: String,
+    pub fee: StdFee,
+    pub memo: String,
+    pub msgs: Vec<M>,
+    #[serde(serialize_with = "as_string")]
+    pub sequence: u64,
+    /// The block height after which the transaction is not valid, or 0 for none.
+    #[serde(serialize_with = "as_string", skip_serializing_if = "is_zero")]
+    pub timeout_height: u64,
+}
+
+impl<M> StdSignDoc<M>
+where
+    M: Serialize,
+{
+    /// The bytes to sign, as `legacytx.StdSignBytes` returns.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a message's implementation of `Serialize` fails, or if a message has a number
+    /// that is not valid in canonical JSON.
+    pub fn sign_bytes(&self) -> io::Result<Vec<u8>> {
+        Ok(to_vec(self)?)
+    }
+}
+
+/// Serialize the given value as JSON into the IO stream, as Go's `encoding/json` writes it with
+/// sorted keys.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails.
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    let formatter = CanonicalFormatter::new()
+        .escaping(Escaping::Html)
+        .unicode_controls()
+        .key_order(CodePointOrder);
+    value.serialize(&mut serde_json::Serializer::with_formatter(
+        writer, formatter,
+    ))
+}
+
+/// Serialize the given value as a JSON byte vector, as Go's `encoding/json` writes it with sorted
+/// keys.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails.
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut data: Vec<u8> = Vec::with_capacity(256);
+    to_writer(&mut data, value)?;
+    Ok(data)
+}
+
+/// Sort the keys of JSON, as `sdk.SortJSON` does.
+///
+/// # Errors
+///
+/// Errors if the bytes are not valid JSON, or if a number is not valid in canonical JSON.
+pub fn sort_json(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let value: Value = serde_json::from_slice(bytes)?;
+    Ok(to_vec(&value)?)
+}
+
+/// Serialize a value as a string, as amino writes 64-bit integers.
+///
+/// Use with `#[serde(serialize_with = "json_canon::cosmos::as_string")]`.
+///
+/// # Errors
+///
+/// Errors if the serializer fails.
+pub fn as_string<T, S>(value: &T, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    serializer.collect_str(value)
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
@@ -0,0 +1,365 @@
+//! Canonical sorting and deduplication of JSON Lines datasets.
+//!
+//! The same record can show up in a dataset with different key order or number spellings.
+//! [`canonicalize_dataset`] canonicalizes each line, so such records become byte-for-byte
+//! duplicates, which can then be removed, and can sort the lines by their canonical bytes, so
+//! that identical datasets result in identical files.
+//!
+//! Sorting a dataset that is bigger than [`DatasetOptions::max_memory`] is an external merge
+//! sort: sorted runs are spilled to temporary files, then merged.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[cfg(feature = "hash")]
+use crate::hash::{hash_bytes, Hash};
+use crate::{
+    stream::{Framing, OnError, RecordError, Records},
+    to_vec,
+};
+
+/// How duplicate records are detected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dedupe {
+    /// Records are duplicates if their canonical bytes are equal.
+    Bytes,
+    /// Records are duplicates if the SHA-256 hashes of their canonical bytes are equal.
+    ///
+    /// Uses less memory than comparing bytes, when not sorting.
+    #[cfg(feature = "hash")]
+    Hash,
+}
+
+/// Options for [`canonicalize_dataset`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DatasetOptions {
+    /// Whether to sort records by their canonical bytes. Otherwise records stay in input order.
+    pub sort: bool,
+    /// Whether, and how, to remove duplicate records.
+    ///
+    /// When sorting, duplicates are adjacent, so are always detected by their bytes.
+    pub dedupe: Option<Dedupe>,
+    /// What to do when a record is not valid.
+    pub on_error: OnError,
+    /// The number of bytes of records to sort in memory, before spilling sorted runs to temporary
+    /// files.
+    pub max_memory: usize,
+    /// The directory for temporary files, or else the system's temporary directory.
+    pub temp_dir: Option<PathBuf>,
+}
+
+impl Default for DatasetOptions {
+    fn default() -> Self {
+        Self {
+            sort: false,
+            dedupe: None,
+            on_error: OnError::Skip,
+            max_memory: 64 * 1024 * 1024,
+            temp_dir: None,
+        }
+    }
+}
+
+/// The outcome of [`canonicalize_dataset`].
+#[derive(Debug, Default)]
+pub struct DatasetReport {
+    /// The number of records written.
+    pub records: usize,
+    /// The number of duplicate records removed.
+    pub duplicates: usize,
+    /// The records that were not valid, and so were skipped.
+    pub errors: Vec<RecordError>,
+}
+
+/// Read a JSON Lines dataset, and write each record canonicalized, optionally sorted and
+/// deduplicated.
+///
+/// # Errors
+///
+/// Errors if either IO stream errors, if a temporary file can't be used, or if a record is not
+/// valid and [`DatasetOptions::on_error`] is set to abort.
+pub fn canonicalize_dataset<R, W>(
+    reader: R,
+    writer: W,
+    options: &DatasetOptions,
+) -> io::Result<DatasetReport>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut dataset = DatasetWriter::new(writer, options.clone());
+    for (index, record) in Records::new(reader, Framing::Lines).enumerate() {
+        match record?.and_then(|value| {
+            to_vec(&value).map_err(|error| RecordError {
+                index,
+                error: error.into(),
+            })
+        }) {
+            Ok(line) => dataset.push(line)?,
+            Err(error) => dataset.push_error(error)?,
+        }
+    }
+    dataset.finish()
+}
+
+/// Writes records which are already canonical, optionally sorted and deduplicated.
+///
+/// This is the second half of [`canonicalize_dataset`], for when records are canonicalized some
+/// other way, such as in parallel.
+pub struct DatasetWriter<W> {
+    options: DatasetOptions,
+    report: DatasetReport,
+    output: Output<W>,
+    seen: Seen,
+    chunk: Chunk,
+    runs: Vec<Run>,
+}
+
+impl<W> DatasetWriter<W>
+where
+    W: Write,
+{
+    pub fn new(writer: W, options: DatasetOptions) -> Self {
+        Self {
+            report: DatasetReport::default(),
+            output: Output {
+                writer,
+                dedupe: options.sort && options.dedupe.is_some(),
+                last: None,
+            },
+            seen: Seen::new(if options.sort { None } else { options.dedupe }),
+            chunk: Chunk::default(),
+            runs: Vec::new(),
+            options,
+        }
+    }
+
+    /// Write a record, which must be the canonical serialization of a value, without a newline.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the IO stream errors, or if a temporary file can't be used.
+    pub fn push(&mut self, line: Vec<u8>) -> io::Result<()> {
+        if self.options.sort {
+            self.chunk.push(line);
+            if self.chunk.bytes >= self.options.max_memory {
+                self.runs.push(self.chunk.spill(&self.options)?);
+            }
+        } else if self.seen.insert(&line) {
+            self.output.write(&line, &mut self.report)?;
+        } else {
+            self.report.duplicates += 1;
+        }
+        Ok(())
+    }
+
+    /// Report a record that is not valid.
+    ///
+    /// # Errors
+    ///
+    /// Errors with the record error if [`DatasetOptions::on_error`] is set to abort.
+    pub fn push_error(&mut self, error: RecordError) -> io::Result<()> {
+        match self.options.on_error {
+            OnError::Skip => {
+                self.report.errors.push(error);
+                Ok(())
+            }
+            OnError::Abort => Err(error.into()),
+        }
+    }
+
+    /// Write any sorted records, and flush the IO stream.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the IO stream errors, or if a temporary file can't be used.
+    pub fn finish(mut self) -> io::Result<DatasetReport> {
+        if self.options.sort {
+            if self.runs.is_empty() {
+                for line in self.chunk.sorted() {
+                    self.output.write(&line, &mut self.report)?;
+                }
+            } else {
+                if !self.chunk.lines.is_empty() {
+                    self.runs.push(self.chunk.spill(&self.options)?);
+                }
+                merge_runs(self.runs, &mut self.output, &mut self.report)?;
+            }
+        }
+        self.output.writer.flush()?;
+        Ok(self.report)
+    }
+}
+
+/// The records seen so far, when deduplicating without sorting.
+enum Seen {
+    None,
+    Bytes(HashSet<Vec<u8>>),
+    #[cfg(feature = "hash")]
+    Hash(HashSet<Hash>),
+}
+
+impl Seen {
+    fn new(dedupe: Option<Dedupe>) -> Self {
+        match dedupe {
+            None => Seen::None,
+            Some(Dedupe::Bytes) => Seen::Bytes(HashSet::new()),
+            #[cfg(feature = "hash")]
+            Some(Dedupe::Hash) => Seen::Hash(HashSet::new()),
+        }
+    }
+
+    /// Insert the line, returning whether it's new.
+    fn insert(&mut self, line: &[u8]) -> bool {
+        match self {
+            Seen::None => true,
+            Seen::Bytes(seen) => seen.insert(line.to_vec()),
+            #[cfg(feature = "hash")]
+            Seen::Hash(seen) => seen.insert(hash_bytes(line)),
+        }
+    }
+}
+
+/// Writes sorted lines, skipping adjacent duplicates if deduplicating.
+struct Output<W> {
+    writer: W,
+    dedupe: bool,
+    last: Option<Vec<u8>>,
+}
+
+impl<W> Output<W>
+where
+    W: Write,
+{
+    fn write(&mut self, line: &[u8], report: &mut DatasetReport) -> io::Result<()> {
+        if self.dedupe && self.last.as_deref() == Some(line) {
+            report.duplicates += 1;
+            return Ok(());
+        }
+        self.writer.write_all(line)?;
+        self.writer.write_all(b"\n")?;
+        report.records += 1;
+        if self.dedupe {
+            self.last = Some(line.to_vec());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Chunk {
+    lines: Vec<Vec<u8>>,
+    bytes: usize,
+}
+
+impl Chunk {
+    fn push(&mut self, line: Vec<u8>) {
+        self.bytes += line.len();
+        self.lines.push(line);
+    }
+
+    fn sorted(&mut self) -> Vec<Vec<u8>> {
+        self.bytes = 0;
+        let mut lines = std::mem::take(&mut self.lines);
+        lines.sort_unstable();
+        lines
+    }
+
+    /// Write the chunk as a sorted run to a temporary file.
+    fn spill(&mut self, options: &DatasetOptions) -> io::Result<Run> {
+        let dir = match &options.temp_dir {
+            Some(dir) => dir.clone(),
+            None => env::temp_dir(),
+        };
+        let (file, path) = create_temp_file(&dir)?;
+        let mut writer = BufWriter::new(file);
+        // Duplicates are kept in the run, to be removed (and counted) when merging.
+        for line in self.sorted() {
+            writer.write_all(&line)?;
+            writer.write_all(b"\n")?;
+        }
+        let mut file = writer.into_inner().map_err(|error| error.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(Run {
+            reader: BufReader::new(file),
+            path,
+        })
+    }
+}
+
+/// A sorted run of lines in a temporary file, which is removed when dropped.
+struct Run {
+    reader: BufReader<File>,
+    path: PathBuf,
+}
+
+impl Run {
+    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut line = Vec::new();
+        if self.reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+        Ok(Some(line))
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// A counter for the names of temporary files, unique within the process.
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+fn create_temp_file(dir: &Path) -> io::Result<(File, PathBuf)> {
+    loop {
+        let count = TEMP_FILES.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("json-canon-{}-{}.jsonl", process::id(), count));
+        match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => return Ok((file, path)),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Merge sorted runs into the output, in order.
+fn merge_runs<W>(
+    mut runs: Vec<Run>,
+    output: &mut Output<W>,
+    report: &mut DatasetReport,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut heap = BinaryHeap::with_capacity(runs.len());
+    for (index, run) in runs.iter_mut().enumerate() {
+        if let Some(line) = run.next_line()? {
+            heap.push(Reverse((line, index)));
+        }
+    }
+    while let Some(Reverse((line, index))) = heap.pop() {
+        output.write(&line, report)?;
+        if let Some(line) = runs[index].next_line()? {
+            heap.push(Reverse((line, index)));
+        }
+    }
+    Ok(())
+}
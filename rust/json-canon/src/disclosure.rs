@@ -0,0 +1,413 @@
+//! Selective disclosure of JSON values, in the style of [SD-JWT].
+//!
+//! An issuer [`conceal`]s object members or array elements: each one is moved into a salted
+//! [`Disclosure`], and replaced in the document by the digest of that disclosure. Object members
+//! are replaced by an entry in the parent object's `"_sd"` array, and array elements are replaced
+//! by an object `{"...": digest}`. A top-level object also gets `"_sd_alg": "sha-256"`, the
+//! hash algorithm of its digests.
+//!
+//! A holder then shares the concealed document along with any subset of the disclosures, and a
+//! verifier calls [`disclose`] to check that each disclosure is referenced by a digest and to
+//! rebuild the disclosed view of the document.
+//!
+//! Unlike SD-JWT, disclosures are always encoded with [`to_vec`], so that the same salt, name, and
+//! value always result in the same disclosure and digest, no matter which implementation made
+//! them.
+//!
+//! [SD-JWT]: https://datatracker.ietf.org/doc/draft-ietf-oauth-selective-disclosure-jwt/
+
+use std::{
+    collections::HashMap,
+    io::{self, Error, ErrorKind},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_json::{from_slice, Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{pointer, to_vec};
+
+/// Key of the array of member digests in a concealed object.
+pub const SD_KEY: &str = "_sd";
+
+/// Key of the element digest in a concealed array element.
+pub const ELEMENT_KEY: &str = "...";
+
+/// Key of the hash algorithm in the top-level object.
+pub const SD_ALG_KEY: &str = "_sd_alg";
+
+/// Name of the only supported hash algorithm.
+pub const SD_ALG: &str = "sha-256";
+
+/// A salted object member or array element.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Disclosure {
+    salt: String,
+    name: Option<String>,
+    value: Value,
+    encoded: String,
+}
+
+impl Disclosure {
+    /// Create a disclosure of an object member, or of an array element if `name` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `name` is reserved, or if `value` can't be serialized.
+    pub fn new(salt: String, name: Option<String>, value: Value) -> io::Result<Self> {
+        if let Some(name) = &name {
+            if name == SD_KEY || name == ELEMENT_KEY {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Disclosure name {:?} is reserved.", name),
+                ));
+            }
+        }
+
+        let bytes = match &name {
+            Some(name) => to_vec(&(&salt, name, &value))?,
+            None => to_vec(&(&salt, &value))?,
+        };
+        let encoded = URL_SAFE_NO_PAD.encode(bytes);
+
+        Ok(Self {
+            salt,
+            name,
+            value,
+            encoded,
+        })
+    }
+
+    /// Decode a disclosure from its base64url encoding.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the disclosure is not base64url, is not a JSON array of the right shape, or is
+    /// not in canonical form.
+    pub fn from_encoded(encoded: &str) -> io::Result<Self> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+
+        let mut array = match from_slice(&bytes)? {
+            Value::Array(array) => array,
+            _ => return Err(invalid_disclosure()),
+        };
+        let value = array.pop().ok_or_else(invalid_disclosure)?;
+        let name = match array.len() {
+            1 => None,
+            2 => match array.pop() {
+                Some(Value::String(name)) => Some(name),
+                _ => return Err(invalid_disclosure()),
+            },
+            _ => return Err(invalid_disclosure()),
+        };
+        let salt = match array.pop() {
+            Some(Value::String(salt)) => salt,
+            _ => return Err(invalid_disclosure()),
+        };
+
+        let disclosure = Self::new(salt, name, value)?;
+        if disclosure.encoded != encoded {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Disclosure is not in canonical form.",
+            ));
+        }
+        Ok(disclosure)
+    }
+
+    /// The salt.
+    pub fn salt(&self) -> &str {
+        &self.salt
+    }
+
+    /// The object member name, or `None` for an array element.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The disclosed value.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// The base64url encoding of the canonical disclosure array.
+    pub fn encoded(&self) -> &str {
+        &self.encoded
+    }
+
+    /// The base64url encoding of the SHA-256 digest of [`Disclosure::encoded`].
+    pub fn digest(&self) -> String {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(self.encoded.as_bytes()))
+    }
+}
+
+/// Conceal the object member or array element at the JSON Pointer, using a random salt.
+///
+/// # Errors
+///
+/// Errors if `pointer` doesn't refer to an object member or array element in `value`.
+pub fn conceal(value: &mut Value, pointer: &str) -> io::Result<Disclosure> {
+    conceal_with_salt(value, pointer, random_salt()?)
+}
+
+/// Conceal the object member or array element at the JSON Pointer, using the given salt.
+///
+/// Salts must be unpredictable for the concealed values to stay private, so this is mostly
+/// useful for reproducible test vectors.
+///
+/// # Errors
+///
+/// Errors if `pointer` doesn't refer to an object member or array element in `value`, or if
+/// `value` has a hash algorithm other than `"sha-256"`.
+pub fn conceal_with_salt(value: &mut Value, pointer: &str, salt: String) -> io::Result<Disclosure> {
+    if let Value::Object(object) = value {
+        check_sd_alg(object.get(SD_ALG_KEY))?;
+    }
+
+    let disclosure = conceal_member(value, pointer, salt)?;
+
+    if let Value::Object(object) = value {
+        object.insert(SD_ALG_KEY.into(), Value::String(SD_ALG.into()));
+    }
+    Ok(disclosure)
+}
+
+fn conceal_member(value: &mut Value, pointer: &str, salt: String) -> io::Result<Disclosure> {
+    let (parent_pointer, token) = pointer::split_last(pointer)?.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "The root value can't be concealed.",
+        )
+    })?;
+    if parent_pointer.is_empty() && token == SD_ALG_KEY {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "The hash algorithm can't be concealed.",
+        ));
+    }
+    let parent = value
+        .pointer_mut(parent_pointer)
+        .ok_or_else(|| not_found(pointer))?;
+
+    match parent {
+        Value::Object(object) => {
+            let name = token.into_owned();
+            if name == SD_KEY || name == ELEMENT_KEY {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Disclosure name {:?} is reserved.", name),
+                ));
+            }
+            let member = object.remove(&name).ok_or_else(|| not_found(pointer))?;
+            let disclosure = Disclosure::new(salt, Some(name), member)?;
+
+            let digests = object
+                .entry(SD_KEY)
+                .or_insert_with(|| Value::Array(Vec::new()));
+            let digests = match digests {
+                Value::Array(digests) => digests,
+                _ => return Err(invalid_sd()),
+            };
+            digests.push(Value::String(disclosure.digest()));
+            digests.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+
+            Ok(disclosure)
+        }
+        Value::Array(array) => {
+            let element = pointer::parse_index(&token)
+                .and_then(|index| array.get_mut(index))
+                .ok_or_else(|| not_found(pointer))?;
+            let disclosure = Disclosure::new(salt, None, element.take())?;
+
+            let mut reference = Map::new();
+            reference.insert(ELEMENT_KEY.into(), Value::String(disclosure.digest()));
+            *element = Value::Object(reference);
+
+            Ok(disclosure)
+        }
+        _ => Err(not_found(pointer)),
+    }
+}
+
+/// Rebuild the disclosed view of a concealed value.
+///
+/// Digests without a matching disclosure are left concealed, which removes them from the view.
+///
+/// # Errors
+///
+/// Errors if the hash algorithm is not `"sha-256"` or is not at the top level, if a disclosure is
+/// not referenced by exactly one digest, if a disclosure is of the wrong kind for its reference,
+/// or if a disclosed member would overwrite an existing member.
+pub fn disclose(value: &Value, disclosures: &[Disclosure]) -> io::Result<Value> {
+    let mut by_digest: HashMap<String, (&Disclosure, bool)> = HashMap::new();
+    for disclosure in disclosures {
+        if by_digest
+            .insert(disclosure.digest(), (disclosure, false))
+            .is_some()
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Disclosure was given more than once.",
+            ));
+        }
+    }
+
+    let mut value = value.clone();
+    if let Value::Object(object) = &mut value {
+        check_sd_alg(object.remove(SD_ALG_KEY).as_ref())?;
+    }
+
+    let value = disclose_value(value, &mut by_digest)?;
+
+    if by_digest.values().any(|(_, is_used)| !is_used) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Disclosure is not referenced by any digest.",
+        ));
+    }
+
+    Ok(value)
+}
+
+fn disclose_value(
+    value: Value,
+    by_digest: &mut HashMap<String, (&Disclosure, bool)>,
+) -> io::Result<Value> {
+    match value {
+        Value::Object(mut object) => {
+            if object.contains_key(SD_ALG_KEY) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Hash algorithm must only be given at the top level.",
+                ));
+            }
+            let digests = match object.remove(SD_KEY) {
+                None => Vec::new(),
+                Some(Value::Array(digests)) => digests,
+                Some(_) => return Err(invalid_sd()),
+            };
+
+            let mut disclosed = Map::new();
+            for (name, member) in object {
+                disclosed.insert(name, disclose_value(member, by_digest)?);
+            }
+
+            for digest in digests {
+                let digest = match digest {
+                    Value::String(digest) => digest,
+                    _ => return Err(invalid_sd()),
+                };
+                let disclosure = match use_disclosure(by_digest, &digest)? {
+                    Some(disclosure) => disclosure,
+                    None => continue,
+                };
+                let name = disclosure.name().ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        "Array element disclosure referenced from an object.",
+                    )
+                })?;
+                if disclosed.contains_key(name) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Disclosed member {:?} already exists.", name),
+                    ));
+                }
+                let member = disclose_value(disclosure.value().clone(), by_digest)?;
+                disclosed.insert(name.to_owned(), member);
+            }
+
+            Ok(Value::Object(disclosed))
+        }
+        Value::Array(array) => {
+            let mut disclosed = Vec::with_capacity(array.len());
+            for element in array {
+                let digest = match &element {
+                    Value::Object(object) if object.len() == 1 => match object.get(ELEMENT_KEY) {
+                        Some(Value::String(digest)) => Some(digest.clone()),
+                        Some(_) => return Err(invalid_sd()),
+                        None => None,
+                    },
+                    _ => None,
+                };
+                let digest = match digest {
+                    Some(digest) => digest,
+                    None => {
+                        disclosed.push(disclose_value(element, by_digest)?);
+                        continue;
+                    }
+                };
+                let disclosure = match use_disclosure(by_digest, &digest)? {
+                    Some(disclosure) => disclosure,
+                    None => continue,
+                };
+                if disclosure.name().is_some() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "Object member disclosure referenced from an array.",
+                    ));
+                }
+                disclosed.push(disclose_value(disclosure.value().clone(), by_digest)?);
+            }
+            Ok(Value::Array(disclosed))
+        }
+        value => Ok(value),
+    }
+}
+
+fn use_disclosure<'a>(
+    by_digest: &mut HashMap<String, (&'a Disclosure, bool)>,
+    digest: &str,
+) -> io::Result<Option<&'a Disclosure>> {
+    match by_digest.get_mut(digest) {
+        None => Ok(None),
+        Some((_, true)) => Err(Error::new(
+            ErrorKind::InvalidData,
+            "Disclosure is referenced by more than one digest.",
+        )),
+        Some((disclosure, is_used)) => {
+            *is_used = true;
+            Ok(Some(*disclosure))
+        }
+    }
+}
+
+fn check_sd_alg(alg: Option<&Value>) -> io::Result<()> {
+    match alg {
+        None => Ok(()),
+        Some(Value::String(alg)) if alg == SD_ALG => Ok(()),
+        Some(_) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Hash algorithm must be {:?}.", SD_ALG),
+        )),
+    }
+}
+
+fn random_salt() -> io::Result<String> {
+    let mut salt = [0; 16];
+    getrandom::getrandom(&mut salt)?;
+    Ok(URL_SAFE_NO_PAD.encode(salt))
+}
+
+fn invalid_disclosure() -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        "Disclosure must be an array of salt, optional name, and value.",
+    )
+}
+
+fn invalid_sd() -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        "Digests must be strings in a `_sd` array or `...` object.",
+    )
+}
+
+fn not_found(pointer: &str) -> Error {
+    Error::new(
+        ErrorKind::NotFound,
+        format!("No object member or array element at {:?}.", pointer),
+    )
+}
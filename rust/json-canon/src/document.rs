@@ -0,0 +1,338 @@
+//! Incremental canonicalization of a document that changes.
+//!
+//! A [`CanonicalDocument`] keeps the canonical serialization (and hash) of every subtree of a
+//! document. Editing the document at a JSON Pointer only invalidates the subtrees on the path from
+//! the edit up to the root, so serializing or hashing the document again only serializes the
+//! changed subtrees, and reuses the rest.
+//!
+//! ```rust
+//! use json_canon::document::CanonicalDocument;
+//! use serde_json::json;
+//! # fn main() -> std::io::Result<()> {
+//!
+//! let mut document = CanonicalDocument::new(json!({ "b": [1, 2], "a": { "c": true } }));
+//! assert_eq!(document.to_vec()?, br#"{"a":{"c":true},"b":[1,2]}"#);
+//!
+//! document.set("/b/1", json!(3))?;
+//! assert_eq!(document.to_vec()?, br#"{"a":{"c":true},"b":[1,3]}"#);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::{self, Error, ErrorKind, Write};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    hash::{hash_bytes, Hash},
+    object::cmp_keys,
+    pointer, splice, to_vec, CanonicalFormatter,
+};
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Scalar(Value),
+    Array(Vec<Node>),
+    Object(Vec<(String, Node)>),
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    kind: Kind,
+    bytes: Option<Vec<u8>>,
+    hash: Option<Hash>,
+}
+
+impl Node {
+    fn new(value: Value) -> Self {
+        let kind = match value {
+            Value::Array(array) => Kind::Array(array.into_iter().map(Node::new).collect()),
+            Value::Object(object) => {
+                let mut members: Vec<(String, Node)> = object
+                    .into_iter()
+                    .map(|(key, member)| (key, Node::new(member)))
+                    .collect();
+                members.sort_by(|(a, _), (b, _)| cmp_keys(a, b));
+                Kind::Object(members)
+            }
+            value => Kind::Scalar(value),
+        };
+        Self {
+            kind,
+            bytes: None,
+            hash: None,
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.bytes = None;
+        self.hash = None;
+    }
+
+    fn to_value(&self) -> Value {
+        match &self.kind {
+            Kind::Scalar(value) => value.clone(),
+            Kind::Array(elements) => Value::Array(elements.iter().map(Node::to_value).collect()),
+            Kind::Object(members) => Value::Object(
+                members
+                    .iter()
+                    .map(|(key, member)| (key.clone(), member.to_value()))
+                    .collect::<Map<_, _>>(),
+            ),
+        }
+    }
+
+    fn bytes(&mut self) -> io::Result<&[u8]> {
+        if self.bytes.is_none() {
+            let bytes = match &mut self.kind {
+                Kind::Scalar(value) => to_vec(value)?,
+                Kind::Array(elements) => {
+                    let elements = elements
+                        .iter_mut()
+                        .map(Node::bytes)
+                        .collect::<io::Result<Vec<_>>>()?;
+                    splice::array(CanonicalFormatter::new(), elements)?
+                }
+                Kind::Object(members) => {
+                    let members = members
+                        .iter_mut()
+                        .map(|(key, member)| Ok((key.as_str(), member.bytes()?)))
+                        .collect::<io::Result<Vec<_>>>()?;
+                    splice::object(CanonicalFormatter::new(), members)?
+                }
+            };
+            self.bytes = Some(bytes);
+        }
+        Ok(self.bytes.as_deref().unwrap_or_default())
+    }
+
+    fn hash(&mut self) -> io::Result<Hash> {
+        if let Some(hash) = self.hash {
+            return Ok(hash);
+        }
+        let hash = hash_bytes(self.bytes()?);
+        self.hash = Some(hash);
+        Ok(hash)
+    }
+
+    fn get(&self, token: &str) -> Option<&Node> {
+        match &self.kind {
+            Kind::Scalar(_) => None,
+            Kind::Array(elements) => pointer::parse_index(token).and_then(|i| elements.get(i)),
+            Kind::Object(members) => members
+                .binary_search_by(|(key, _)| cmp_keys(key, token))
+                .ok()
+                .map(|index| &members[index].1),
+        }
+    }
+
+    fn get_mut(&mut self, token: &str) -> Option<&mut Node> {
+        match &mut self.kind {
+            Kind::Scalar(_) => None,
+            Kind::Array(elements) => {
+                pointer::parse_index(token).and_then(move |i| elements.get_mut(i))
+            }
+            Kind::Object(members) => members
+                .binary_search_by(|(key, _)| cmp_keys(key, token))
+                .ok()
+                .map(move |index| &mut members[index].1),
+        }
+    }
+}
+
+/// A JSON document that caches the canonical serialization and hash of each subtree.
+#[derive(Clone, Debug)]
+pub struct CanonicalDocument {
+    root: Node,
+}
+
+impl CanonicalDocument {
+    pub fn new(value: Value) -> Self {
+        Self {
+            root: Node::new(value),
+        }
+    }
+
+    /// The document as a value.
+    pub fn to_value(&self) -> Value {
+        self.root.to_value()
+    }
+
+    /// The value at the JSON Pointer, if any.
+    pub fn get(&self, pointer: &str) -> Option<Value> {
+        let tokens = pointer::parse(pointer).ok()?;
+        let mut node = &self.root;
+        for token in &tokens {
+            node = node.get(token)?;
+        }
+        Some(node.to_value())
+    }
+
+    /// Set the value at the JSON Pointer, returning the value it replaced, if any.
+    ///
+    /// If the pointer refers to a member of an object, the member is inserted or replaced. If the
+    /// pointer refers to an element of an array, the element is replaced, or if the last token is
+    /// `-`, the value is appended to the array.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the parent of the pointer doesn't exist, or is not an object or array.
+    pub fn set(&mut self, pointer: &str, value: Value) -> io::Result<Option<Value>> {
+        let (parent, token) = match self.parent_mut(pointer)? {
+            Some(parent) => parent,
+            None => {
+                let previous = std::mem::replace(&mut self.root, Node::new(value));
+                return Ok(Some(previous.to_value()));
+            }
+        };
+        let node = Node::new(value);
+        match &mut parent.kind {
+            Kind::Object(members) => {
+                match members.binary_search_by(|(key, _)| cmp_keys(key, &token)) {
+                    Ok(index) => {
+                        let previous = std::mem::replace(&mut members[index].1, node);
+                        Ok(Some(previous.to_value()))
+                    }
+                    Err(index) => {
+                        members.insert(index, (token, node));
+                        Ok(None)
+                    }
+                }
+            }
+            Kind::Array(elements) if token == "-" => {
+                elements.push(node);
+                Ok(None)
+            }
+            Kind::Array(elements) => {
+                let element = pointer::parse_index(&token)
+                    .and_then(|index| elements.get_mut(index))
+                    .ok_or_else(|| not_found(pointer))?;
+                let previous = std::mem::replace(element, node);
+                Ok(Some(previous.to_value()))
+            }
+            Kind::Scalar(_) => Err(not_found(pointer)),
+        }
+    }
+
+    /// Remove the value at the JSON Pointer, returning it.
+    ///
+    /// Removing an element of an array shifts the following elements down.
+    ///
+    /// # Errors
+    ///
+    /// Errors if there is no value at the pointer, or if the pointer is the root.
+    pub fn remove(&mut self, pointer: &str) -> io::Result<Value> {
+        let (parent, token) = self.parent_mut(pointer)?.ok_or_else(|| {
+            Error::new(ErrorKind::InvalidInput, "The root value can't be removed.")
+        })?;
+        match &mut parent.kind {
+            Kind::Object(members) => {
+                let index = members
+                    .binary_search_by(|(key, _)| cmp_keys(key, &token))
+                    .map_err(|_| not_found(pointer))?;
+                Ok(members.remove(index).1.to_value())
+            }
+            Kind::Array(elements) => {
+                let index = pointer::parse_index(&token)
+                    .filter(|index| *index < elements.len())
+                    .ok_or_else(|| not_found(pointer))?;
+                Ok(elements.remove(index).to_value())
+            }
+            Kind::Scalar(_) => Err(not_found(pointer)),
+        }
+    }
+
+    /// The parent of the JSON Pointer and the last token, invalidating the cache on the path
+    /// from the root to the parent. Returns `None` for the root pointer.
+    ///
+    /// The path is resolved before any cache is invalidated, so a pointer whose parent doesn't
+    /// exist, or is not an object or array, leaves the cache as it was.
+    fn parent_mut(&mut self, pointer: &str) -> io::Result<Option<(&mut Node, String)>> {
+        let mut tokens = pointer::parse(pointer)?;
+        let token = match tokens.pop() {
+            Some(token) => token.into_owned(),
+            None => return Ok(None),
+        };
+
+        let mut node = &self.root;
+        for parent_token in &tokens {
+            node = node.get(parent_token).ok_or_else(|| not_found(pointer))?;
+        }
+        if let Kind::Scalar(_) = node.kind {
+            return Err(not_found(pointer));
+        }
+
+        let mut node = &mut self.root;
+        node.invalidate();
+        for parent_token in &tokens {
+            node = node
+                .get_mut(parent_token)
+                .ok_or_else(|| not_found(pointer))?;
+            node.invalidate();
+        }
+        Ok(Some((node, token)))
+    }
+
+    /// The canonical serialization of the document.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a number is not valid in canonical JSON.
+    pub fn canonical_bytes(&mut self) -> io::Result<&[u8]> {
+        self.root.bytes()
+    }
+
+    /// The canonical serialization of the document, as a byte vector.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a number is not valid in canonical JSON.
+    pub fn to_vec(&mut self) -> io::Result<Vec<u8>> {
+        Ok(self.canonical_bytes()?.to_vec())
+    }
+
+    /// Write the canonical serialization of the document into the IO stream.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a number is not valid in canonical JSON, or if the IO stream errors.
+    pub fn write_to<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        writer.write_all(self.canonical_bytes()?)
+    }
+
+    /// The SHA-256 hash of the canonical serialization of the document.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a number is not valid in canonical JSON.
+    pub fn hash(&mut self) -> io::Result<Hash> {
+        self.root.hash()
+    }
+
+    /// The SHA-256 hash of the canonical serialization of the value at the JSON Pointer.
+    ///
+    /// # Errors
+    ///
+    /// Errors if there is no value at the pointer, or if a number is not valid in canonical JSON.
+    pub fn hash_at(&mut self, pointer: &str) -> io::Result<Hash> {
+        let tokens = pointer::parse(pointer)?;
+        let mut node = &mut self.root;
+        for token in &tokens {
+            node = node.get_mut(token).ok_or_else(|| not_found(pointer))?;
+        }
+        node.hash()
+    }
+}
+
+impl From<Value> for CanonicalDocument {
+    fn from(value: Value) -> Self {
+        Self::new(value)
+    }
+}
+
+fn not_found(pointer: &str) -> Error {
+    Error::new(ErrorKind::NotFound, format!("No value at {:?}.", pointer))
+}
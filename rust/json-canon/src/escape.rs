@@ -0,0 +1,159 @@
+//! Escaping profiles for strings, other than JCS.
+//!
+//! RFC 8785 writes strings as raw UTF-8, escaping only what JSON requires. Some transports
+//! garble non-ASCII bytes, so [`Escaping::Ascii`] escapes every non-ASCII code point as
+//! `\uXXXX`, with a surrogate pair for a code point outside the Basic Multilingual Plane.
+//!
+//! JSON embedded in HTML `<script>` tags or JavaScript source must not contain `<`, `>`, or `&`
+//! (which could close the tag or start an entity), or U+2028 and U+2029 (which end a line in
+//! older JavaScript), so [`Escaping::Html`] escapes those, as Go's `encoding/json` does.
+//!
+//! Keys are still sorted by their unescaped value, in the formatter's
+//! [`KeyOrder`](crate::order::KeyOrder) (UTF-16 code units, unless changed, as the
+//! `cosmos` profile does), and numbers are still written as in RFC 8785, but the
+//! bytes are **not** JCS: a hash or signature over bytes in one profile
+//! doesn't match the bytes in another. Check bytes against the profile they were written in, with
+//! [`is_canonical`].
+//!
+//! ```rust
+//! use json_canon::escape::{is_canonical, to_string, Escaping};
+//! use serde_json::json;
+//! # fn main() -> std::io::Result<()> {
+//!
+//! let value = json!({ "z": "😀", "é": "€" });
+//! let ascii = to_string(&value, Escaping::Ascii)?;
+//! assert_eq!(ascii, r#"{"z":"\ud83d\ude00","\u00e9":"\u20ac"}"#);
+//! assert!(is_canonical(ascii.as_bytes(), Escaping::Ascii)?);
+//! assert!(!is_canonical(ascii.as_bytes(), Escaping::Jcs)?);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+use serde_json::{Result, Serializer, Value};
+
+use crate::CanonicalFormatter;
+
+static HEX_CHARS: [u8; 16] = *b"0123456789abcdef";
+
+/// How strings are escaped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Escaping {
+    /// Escape only what JSON requires, as specified in RFC 8785.
+    Jcs,
+    /// Also escape every non-ASCII code point, so the output is ASCII.
+    Ascii,
+    /// Also escape `<`, `>`, `&`, U+2028, and U+2029, so the output is safe to embed in HTML and
+    /// JavaScript.
+    Html,
+}
+
+impl Default for Escaping {
+    fn default() -> Self {
+        Escaping::Jcs
+    }
+}
+
+impl Escaping {
+    /// Whether the character, which JSON doesn't require to be escaped, is escaped.
+    fn escapes(self, c: char) -> bool {
+        match self {
+            Escaping::Jcs => false,
+            Escaping::Ascii => !c.is_ascii(),
+            Escaping::Html => matches!(c, '<' | '>' | '&' | '\u{2028}' | '\u{2029}'),
+        }
+    }
+
+    /// Write a string fragment, which has no characters that JSON requires to be escaped.
+    pub(crate) fn write_fragment<W>(self, writer: &mut W, fragment: &str) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        if self == Escaping::Jcs {
+            return writer.write_all(fragment.as_bytes());
+        }
+        let mut start = 0;
+        for (index, c) in fragment.char_indices() {
+            if !self.escapes(c) {
+                continue;
+            }
+            writer.write_all(&fragment.as_bytes()[start..index])?;
+            let mut units = [0; 2];
+            for unit in c.encode_utf16(&mut units) {
+                write_unicode_escape(writer, *unit)?;
+            }
+            start = index + c.len_utf8();
+        }
+        writer.write_all(&fragment.as_bytes()[start..])
+    }
+}
+
+fn write_unicode_escape<W>(writer: &mut W, unit: u16) -> io::Result<()>
+where
+    W: Write + ?Sized,
+{
+    writer.write_all(&[
+        b'\\',
+        b'u',
+        HEX_CHARS[(unit >> 12) as usize],
+        HEX_CHARS[(unit >> 8 & 0xF) as usize],
+        HEX_CHARS[(unit >> 4 & 0xF) as usize],
+        HEX_CHARS[(unit & 0xF) as usize],
+    ])
+}
+
+/// Serialize the given value as JSON into the IO stream, with the escaping profile.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails.
+pub fn to_writer<W, T>(writer: W, value: &T, escaping: Escaping) -> Result<()>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    value.serialize(&mut Serializer::with_formatter(
+        writer,
+        CanonicalFormatter::new().escaping(escaping),
+    ))
+}
+
+/// Serialize the given value as a JSON byte vector, with the escaping profile.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails.
+pub fn to_vec<T>(value: &T, escaping: Escaping) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut data: Vec<u8> = Vec::with_capacity(128);
+    to_writer(&mut data, value, escaping)?;
+    Ok(data)
+}
+
+/// Serialize the given value as a String of JSON, with the escaping profile.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails.
+pub fn to_string<T>(value: &T, escaping: Escaping) -> Result<String>
+where
+    T: Serialize + ?Sized,
+{
+    let data = to_vec(value, escaping)?;
+    Ok(unsafe { String::from_utf8_unchecked(data) })
+}
+
+/// Whether the bytes are exactly the canonical serialization of the JSON they contain, with the
+/// escaping profile.
+///
+/// # Errors
+///
+/// Errors if the bytes are not valid JSON, or if a number is not valid in canonical JSON.
+pub fn is_canonical(bytes: &[u8], escaping: Escaping) -> io::Result<bool> {
+    let value: Value = serde_json::from_slice(bytes)?;
+    Ok(to_vec(&value, escaping)? == bytes)
+}
@@ -0,0 +1,221 @@
+//! A Git filter, using the [long-running filter process] protocol.
+//!
+//! The `clean` step (when staging a file) writes the canonical serialization of the file, so
+//! files are always committed in canonical form. The `smudge` step (when checking out a file)
+//! pretty-prints the canonical serialization, so the file is easy to edit.
+//!
+//! ```shell
+//! git config filter.json-canon.process "json-canon git-filter"
+//! git config filter.json-canon.required true
+//! echo '*.json filter=json-canon' >> .gitattributes
+//! ```
+//!
+//! [long-running filter process]: https://git-scm.com/docs/gitattributes#_long_running_filter_process
+
+use std::io::{self, Error, ErrorKind, Read, Write};
+
+use serde_json::Value;
+
+use crate::{to_writer, to_writer_pretty};
+
+/// The maximum length of the data in a packet.
+const MAX_PACKET_DATA: usize = 65516;
+
+enum Packet {
+    Flush,
+    Data(Vec<u8>),
+}
+
+/// Run the filter process, reading requests from Git and writing responses, until Git closes the
+/// input.
+///
+/// # Errors
+///
+/// Errors if either IO stream errors, or if Git doesn't follow the protocol. A file which can't
+/// be filtered is not an error: the filter responds with an error status for the file, and
+/// carries on.
+pub fn filter_process<R, W>(mut reader: R, mut writer: W) -> io::Result<()>
+where
+    R: Read,
+    W: Write,
+{
+    handshake(&mut reader, &mut writer)?;
+    while let Some(headers) = read_list(&mut reader)? {
+        let command = header(&headers, "command");
+        let content = read_content(&mut reader)?;
+        let filtered = match command {
+            Some("clean") => clean(&content),
+            Some("smudge") => smudge(&content),
+            _ => Err(protocol_error("Unknown command.")),
+        };
+        match filtered {
+            Ok(filtered) => {
+                write_text(&mut writer, "status=success")?;
+                write_flush(&mut writer)?;
+                write_content(&mut writer, &filtered)?;
+                write_flush(&mut writer)?;
+                // The status is unchanged.
+                write_flush(&mut writer)?;
+            }
+            Err(_) => {
+                write_text(&mut writer, "status=error")?;
+                write_flush(&mut writer)?;
+            }
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Canonicalize the file.
+fn clean(content: &[u8]) -> io::Result<Vec<u8>> {
+    let value: Value = serde_json::from_slice(content)?;
+    let mut data = Vec::with_capacity(content.len());
+    to_writer(&mut data, &value)?;
+    Ok(data)
+}
+
+/// Pretty-print the canonical serialization of the file, with a trailing newline.
+fn smudge(content: &[u8]) -> io::Result<Vec<u8>> {
+    let value: Value = serde_json::from_slice(content)?;
+    let mut data = Vec::with_capacity(content.len() * 2);
+    to_writer_pretty(&mut data, &value)?;
+    data.push(b'\n');
+    Ok(data)
+}
+
+fn handshake<R, W>(reader: &mut R, writer: &mut W) -> io::Result<()>
+where
+    R: Read,
+    W: Write,
+{
+    let welcome = read_list(reader)?.unwrap_or_default();
+    if welcome.first().map(String::as_str) != Some("git-filter-client")
+        || !welcome.iter().any(|line| line == "version=2")
+    {
+        return Err(protocol_error(
+            "Expected a git-filter-client with version 2.",
+        ));
+    }
+    write_text(writer, "git-filter-server")?;
+    write_text(writer, "version=2")?;
+    write_flush(writer)?;
+    writer.flush()?;
+
+    let capabilities = read_list(reader)?.unwrap_or_default();
+    for capability in ["capability=clean", "capability=smudge"] {
+        if capabilities.iter().any(|line| line == capability) {
+            write_text(writer, capability)?;
+        }
+    }
+    write_flush(writer)?;
+    writer.flush()
+}
+
+/// The value of the header with the key, as in `key=value`.
+fn header<'a>(headers: &'a [String], key: &str) -> Option<&'a str> {
+    headers.iter().find_map(|line| {
+        line.strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix('='))
+    })
+}
+
+/// Read a packet, or `None` at the end of the input.
+fn read_packet<R>(reader: &mut R) -> io::Result<Option<Packet>>
+where
+    R: Read,
+{
+    let mut length = [0; 4];
+    let mut read = 0;
+    while read < length.len() {
+        match reader.read(&mut length[read..])? {
+            0 if read == 0 => return Ok(None),
+            0 => return Err(ErrorKind::UnexpectedEof.into()),
+            n => read += n,
+        }
+    }
+    let length = std::str::from_utf8(&length)
+        .ok()
+        .and_then(|length| usize::from_str_radix(length, 16).ok())
+        .ok_or_else(|| protocol_error("Invalid packet length."))?;
+    match length {
+        0 => Ok(Some(Packet::Flush)),
+        1..=4 => Err(protocol_error("Invalid packet length.")),
+        length => {
+            let mut data = vec![0; length - 4];
+            reader.read_exact(&mut data)?;
+            Ok(Some(Packet::Data(data)))
+        }
+    }
+}
+
+/// Read text packets up to a flush packet, or `None` at the end of the input.
+fn read_list<R>(reader: &mut R) -> io::Result<Option<Vec<String>>>
+where
+    R: Read,
+{
+    let mut lines = Vec::new();
+    loop {
+        match read_packet(reader)? {
+            None if lines.is_empty() => return Ok(None),
+            None => return Err(ErrorKind::UnexpectedEof.into()),
+            Some(Packet::Flush) => return Ok(Some(lines)),
+            Some(Packet::Data(data)) => {
+                let line = String::from_utf8(data)
+                    .map_err(|_| protocol_error("Text packet is not valid UTF-8."))?;
+                lines.push(line.strip_suffix('\n').unwrap_or(&line).to_owned());
+            }
+        }
+    }
+}
+
+/// Read data packets up to a flush packet.
+fn read_content<R>(reader: &mut R) -> io::Result<Vec<u8>>
+where
+    R: Read,
+{
+    let mut content = Vec::new();
+    loop {
+        match read_packet(reader)? {
+            None => return Err(ErrorKind::UnexpectedEof.into()),
+            Some(Packet::Flush) => return Ok(content),
+            Some(Packet::Data(data)) => content.extend_from_slice(&data),
+        }
+    }
+}
+
+fn write_packet<W>(writer: &mut W, data: &[u8]) -> io::Result<()>
+where
+    W: Write + ?Sized,
+{
+    write!(writer, "{:04x}", data.len() + 4)?;
+    writer.write_all(data)
+}
+
+fn write_text<W>(writer: &mut W, text: &str) -> io::Result<()>
+where
+    W: Write + ?Sized,
+{
+    write_packet(writer, format!("{}\n", text).as_bytes())
+}
+
+fn write_content<W>(writer: &mut W, content: &[u8]) -> io::Result<()>
+where
+    W: Write + ?Sized,
+{
+    for chunk in content.chunks(MAX_PACKET_DATA) {
+        write_packet(writer, chunk)?;
+    }
+    Ok(())
+}
+
+fn write_flush<W>(writer: &mut W) -> io::Result<()>
+where
+    W: Write + ?Sized,
+{
+    writer.write_all(b"0000")
+}
+
+fn protocol_error(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_owned())
+}
@@ -0,0 +1,62 @@
+//! Hashes of canonical JSON.
+
+use serde::Serialize;
+use serde_json::Result;
+use sha2::{Digest, Sha256};
+
+use crate::to_writer;
+
+/// A SHA-256 hash.
+pub type Hash = [u8; 32];
+
+/// Hash the given value: the SHA-256 of its canonical serialization.
+///
+/// The value is serialized straight into the hasher, without buffering.
+///
+/// # Errors
+///
+/// Hashing can fail if `T`'s implementation of `Serialize` fails.
+#[inline]
+pub fn to_hash<T>(value: &T) -> Result<Hash>
+where
+    T: Serialize + ?Sized,
+{
+    let mut hasher = Sha256::new();
+    to_writer(&mut hasher, value)?;
+    Ok(hasher.finalize().into())
+}
+
+/// Hash bytes that are already canonical JSON.
+#[inline]
+pub(crate) fn hash_bytes(bytes: &[u8]) -> Hash {
+    Sha256::digest(bytes).into()
+}
+
+/// Lowercase hexadecimal encoding of the hash.
+pub fn to_hex(hash: &Hash) -> String {
+    static HEX_CHARS: [u8; 16] = *b"0123456789abcdef";
+
+    let mut hex = String::with_capacity(hash.len() * 2);
+    for byte in hash {
+        hex.push(HEX_CHARS[(byte >> 4) as usize] as char);
+        hex.push(HEX_CHARS[(byte & 0xF) as usize] as char);
+    }
+    hex
+}
+
+/// Decode a hash from hexadecimal.
+///
+/// Returns `None` if `hex` is not 64 hexadecimal digits.
+pub fn from_hex(hex: &str) -> Option<Hash> {
+    let hex = hex.as_bytes();
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut hash = [0; 32];
+    for (byte, pair) in hash.iter_mut().zip(hex.chunks(2)) {
+        let high = (pair[0] as char).to_digit(16)?;
+        let low = (pair[1] as char).to_digit(16)?;
+        *byte = (high << 4 | low) as u8;
+    }
+    Some(hash)
+}
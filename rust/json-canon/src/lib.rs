@@ -34,7 +34,49 @@
 //! `serde_json` deserializes `f64::NAN` and `f64::Infinite` as `None`, so if given a Rust struct with these values, the `json-canon` will currently output `"null"`.
 //!
 
+pub mod compare;
+#[cfg(feature = "cosmos")]
+pub mod cosmos;
+pub mod dataset;
+#[cfg(feature = "disclosure")]
+pub mod disclosure;
+#[cfg(feature = "hash")]
+pub mod document;
+pub mod escape;
+#[cfg(feature = "git-filter")]
+pub mod git_filter;
+#[cfg(feature = "hash")]
+pub mod hash;
+pub mod lint;
+pub mod merge;
+pub mod merge_patch;
+#[cfg(feature = "hash")]
+pub mod merkle;
+#[cfg(feature = "normalize")]
+pub mod normalize;
 mod object;
+pub mod order;
+pub mod partial;
+pub mod patch;
+mod pointer;
+pub mod select;
 mod ser;
+pub mod set;
+pub mod sorted;
+#[cfg(feature = "hash")]
+mod splice;
+#[cfg(feature = "hash")]
+pub mod store;
+pub mod stream;
+mod value;
 
-pub use self::ser::{to_string, to_vec, to_writer};
+pub use self::merge::merge;
+#[cfg(feature = "hash")]
+pub use self::merge_patch::merge_patch_digest;
+pub use self::merge_patch::{merge_patch, merge_patch_to_writer};
+pub use self::partial::{canonical_subtree, canonicalize_at};
+pub use self::patch::{diff, Patch};
+pub use self::ser::{
+    to_string, to_string_pretty, to_vec, to_vec_pretty, to_writer, to_writer_pretty,
+    CanonicalFormatter,
+};
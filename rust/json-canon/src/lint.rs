@@ -0,0 +1,461 @@
+//! Find sequences from collections with no deterministic order.
+//!
+//! `serde` serializes a `HashSet` as a sequence in its iteration order, which changes from run
+//! to run, so the canonical serialization changes too, with no error. [`unordered`] walks a value
+//! and reports each such sequence, by the name of its type, so it can be wrapped in
+//! [`CanonicalSet`](crate::sorted::CanonicalSet).
+//!
+//! In debug builds, [`to_writer`](crate::to_writer) (and so [`to_vec`](crate::to_vec) and
+//! [`to_string`](crate::to_string)) prints a warning to stderr the first time each type of
+//! collection is found on a thread. [`set_warnings`] turns the warnings off. Release builds don't
+//! look.
+//!
+//! Type names are not guaranteed to be stable, so this is a lint, not a check: it knows the sets
+//! of `std`, `hashbrown`, and `ahash`, and can only see types that `serde` passes by name, such
+//! as struct fields, elements, and map values.
+//!
+//! ```rust
+//! use std::collections::HashSet;
+//!
+//! use json_canon::lint::unordered;
+//! use serde_derive::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct User {
+//!     roles: HashSet<String>,
+//! }
+//!
+//! let user = User {
+//!     roles: HashSet::new(),
+//! };
+//! let found = unordered(&user);
+//! assert_eq!(found.len(), 1);
+//! assert_eq!(found[0].path, "/roles");
+//! ```
+
+#[cfg(debug_assertions)]
+use std::cell::RefCell;
+use std::{
+    any::type_name,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use serde::{ser, Serialize};
+use serde_json::{Error, Value};
+
+use crate::pointer;
+
+/// Collections with no deterministic order, which `serde` serializes as a sequence.
+static UNORDERED_TYPES: [&str; 3] = [
+    "std::collections::hash::set::HashSet",
+    "hashbrown::set::HashSet",
+    "ahash::hash_set::AHashSet",
+];
+
+/// Whether serializing in debug builds warns of collections with no deterministic order.
+static WARNINGS: AtomicBool = AtomicBool::new(true);
+
+#[cfg(debug_assertions)]
+thread_local! {
+    /// The names of the types already warned of on this thread.
+    static WARNED: RefCell<Vec<&'static str>> = RefCell::new(Vec::new());
+}
+
+/// A sequence from a collection with no deterministic order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Unordered {
+    /// The JSON Pointer to the sequence.
+    pub path: String,
+    /// The name of the type of the collection.
+    pub type_name: &'static str,
+}
+
+/// Find the sequences in the value from collections with no deterministic order.
+pub fn unordered<T>(value: &T) -> Vec<Unordered>
+where
+    T: Serialize + ?Sized,
+{
+    let mut found = Vec::new();
+    // The walk only fails if `T`'s implementation of `Serialize` fails, which serializing will
+    // report.
+    let _ = visit(value, String::new(), &mut found);
+    found
+}
+
+/// Turn on or off the warnings printed when serializing in debug builds, for every thread.
+///
+/// They are on by default.
+pub fn set_warnings(enabled: bool) {
+    WARNINGS.store(enabled, Ordering::Relaxed);
+}
+
+/// Print a warning to stderr for each type of collection with no deterministic order in the
+/// value, unless it was already warned of on this thread.
+#[cfg(debug_assertions)]
+pub(crate) fn warn<T>(value: &T)
+where
+    T: Serialize + ?Sized,
+{
+    if !WARNINGS.load(Ordering::Relaxed) {
+        return;
+    }
+    for Unordered { path, type_name } in unordered(value) {
+        let is_new = WARNED.with(|warned| {
+            let mut warned = warned.borrow_mut();
+            let is_new = !warned.contains(&type_name);
+            if is_new {
+                warned.push(type_name);
+            }
+            is_new
+        });
+        if is_new {
+            eprintln!(
+                "json-canon: warning: the array at {:?} is a {}, which has no deterministic \
+                 order; use json_canon::sorted::CanonicalSet",
+                path, type_name
+            );
+        }
+    }
+}
+
+fn visit<T>(value: &T, path: String, found: &mut Vec<Unordered>) -> Result<(), Error>
+where
+    T: Serialize + ?Sized,
+{
+    let name = type_name::<T>();
+    let base = name.trim_start_matches('&').trim_start_matches("mut ");
+    let base = base.split('<').next().unwrap_or(base);
+    if UNORDERED_TYPES.contains(&base) {
+        found.push(Unordered {
+            path: path.clone(),
+            type_name: name,
+        });
+    }
+    value.serialize(Walker { path, found })
+}
+
+/// A serializer which writes nothing, but visits every value that `serde` passes by type.
+struct Walker<'a> {
+    path: String,
+    found: &'a mut Vec<Unordered>,
+}
+
+/// The members of a sequence, map, or struct.
+struct Members<'a> {
+    path: String,
+    found: &'a mut Vec<Unordered>,
+    index: usize,
+    key: String,
+}
+
+impl<'a> Walker<'a> {
+    fn members(self, path: String) -> Members<'a> {
+        Members {
+            path,
+            found: self.found,
+            index: 0,
+            key: String::new(),
+        }
+    }
+}
+
+impl<'a> Members<'a> {
+    fn element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let path = format!("{}/{}", self.path, self.index);
+        self.index += 1;
+        visit(value, path, self.found)
+    }
+
+    fn field<T>(&mut self, key: &str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        visit(value, pointer::join(&self.path, key), self.found)
+    }
+}
+
+impl<'a> ser::Serializer for Walker<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Members<'a>;
+    type SerializeTuple = Members<'a>;
+    type SerializeTupleStruct = Members<'a>;
+    type SerializeTupleVariant = Members<'a>;
+    type SerializeMap = Members<'a>;
+    type SerializeStruct = Members<'a>;
+    type SerializeStructVariant = Members<'a>;
+
+    fn serialize_bool(self, _: bool) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_i8(self, _: i8) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_i16(self, _: i16) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_i32(self, _: i32) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_u8(self, _: u8) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_u16(self, _: u16) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_u32(self, _: u32) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_char(self, _: char) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_str(self, _: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        visit(value, self.path, self.found)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        visit(value, self.path, self.found)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        visit(value, pointer::join(&self.path, variant), self.found)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Members<'a>, Error> {
+        let path = self.path.clone();
+        Ok(self.members(path))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Members<'a>, Error> {
+        let path = self.path.clone();
+        Ok(self.members(path))
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Members<'a>, Error> {
+        let path = self.path.clone();
+        Ok(self.members(path))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Members<'a>, Error> {
+        let path = pointer::join(&self.path, variant);
+        Ok(self.members(path))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Members<'a>, Error> {
+        let path = self.path.clone();
+        Ok(self.members(path))
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Members<'a>, Error> {
+        let path = self.path.clone();
+        Ok(self.members(path))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Members<'a>, Error> {
+        let path = pointer::join(&self.path, variant);
+        Ok(self.members(path))
+    }
+}
+
+impl<'a> ser::SerializeSeq for Members<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTuple for Members<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for Members<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for Members<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeMap for Members<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        // Keys are strings in JSON, or numbers written as strings.
+        self.key = match serde_json::to_value(key)? {
+            Value::String(key) => key,
+            key => key.to_string(),
+        };
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = std::mem::take(&mut self.key);
+        self.field(&key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStruct for Members<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for Members<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
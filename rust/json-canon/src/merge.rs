@@ -0,0 +1,176 @@
+//! Structural three-way merge of JSON documents.
+//!
+//! [`merge`] merges the changes from a common `base` to each of `ours` and `theirs`. Object
+//! members are merged recursively, so concurrent edits to different members never conflict.
+//! Arrays, and all other values, are merged as a whole: if only one side changed a value, that
+//! change wins, and if both sides changed a value differently, that's a [`Conflict`].
+//!
+//! Values are compared by their canonical serialization, so `1.0` and `1` are the same value.
+
+use std::io::{self, Error, ErrorKind, Write};
+
+use serde_json::{Map, Value};
+
+use crate::{object::cmp_keys, pointer, to_writer, value::canonical_eq};
+
+/// A value changed differently on both sides of a merge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    /// JSON Pointer to the value.
+    pub pointer: String,
+    /// The value in the base, or `None` if absent.
+    pub base: Option<Value>,
+    /// The value in ours, or `None` if removed.
+    pub ours: Option<Value>,
+    /// The value in theirs, or `None` if removed.
+    pub theirs: Option<Value>,
+}
+
+/// The result of a three-way merge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Merge {
+    value: Value,
+    conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    /// Whether the merge has no conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// The conflicts, in canonical order of their pointers.
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    /// The merged value, where each conflict is resolved as ours.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// The merged value, or the conflicts.
+    ///
+    /// # Errors
+    ///
+    /// Errors with the conflicts if the merge is not clean.
+    pub fn into_result(self) -> Result<Value, Vec<Conflict>> {
+        if self.conflicts.is_empty() {
+            Ok(self.value)
+        } else {
+            Err(self.conflicts)
+        }
+    }
+
+    /// Write the canonical serialization of a clean merge into the IO stream.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the merge is not clean, or if serialization fails.
+    pub fn to_writer<W>(&self, writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        if let Some(conflict) = self.conflicts.first() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Merge has {} conflict(s), first at {:?}.",
+                    self.conflicts.len(),
+                    conflict.pointer
+                ),
+            ));
+        }
+        Ok(to_writer(writer, &self.value)?)
+    }
+
+    /// The canonical serialization of a clean merge.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the merge is not clean, or if serialization fails.
+    pub fn to_vec(&self) -> io::Result<Vec<u8>> {
+        let mut data: Vec<u8> = Vec::with_capacity(128);
+        self.to_writer(&mut data)?;
+        Ok(data)
+    }
+}
+
+/// Merge the changes from `base` to `ours` and from `base` to `theirs`.
+pub fn merge(base: &Value, ours: &Value, theirs: &Value) -> Merge {
+    let mut conflicts = Vec::new();
+    let value = merge_value(Some(base), Some(ours), Some(theirs), "", &mut conflicts)
+        .unwrap_or(Value::Null);
+    Merge { value, conflicts }
+}
+
+fn option_eq(a: Option<&Value>, b: Option<&Value>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => canonical_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn merge_value(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    pointer: &str,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<Value> {
+    if option_eq(ours, theirs) || option_eq(base, theirs) {
+        return ours.cloned();
+    }
+    if option_eq(base, ours) {
+        return theirs.cloned();
+    }
+
+    match (base, ours, theirs) {
+        (Some(Value::Object(base)), Some(Value::Object(ours)), Some(Value::Object(theirs))) => {
+            Some(merge_objects(Some(base), ours, theirs, pointer, conflicts))
+        }
+        (None, Some(Value::Object(ours)), Some(Value::Object(theirs))) => {
+            Some(merge_objects(None, ours, theirs, pointer, conflicts))
+        }
+        _ => {
+            conflicts.push(Conflict {
+                pointer: pointer.to_owned(),
+                base: base.cloned(),
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+            ours.cloned()
+        }
+    }
+}
+
+fn merge_objects(
+    base: Option<&Map<String, Value>>,
+    ours: &Map<String, Value>,
+    theirs: &Map<String, Value>,
+    pointer: &str,
+    conflicts: &mut Vec<Conflict>,
+) -> Value {
+    let mut keys: Vec<&String> = ours.keys().chain(theirs.keys()).collect();
+    if let Some(base) = base {
+        keys.extend(base.keys());
+    }
+    keys.sort_by(|a, b| cmp_keys(a, b));
+    keys.dedup();
+
+    let mut merged = Map::new();
+    for key in keys {
+        let member = merge_value(
+            base.and_then(|base| base.get(key)),
+            ours.get(key),
+            theirs.get(key),
+            &pointer::join(pointer, key),
+            conflicts,
+        );
+        if let Some(member) = member {
+            merged.insert(key.clone(), member);
+        }
+    }
+    Value::Object(merged)
+}
@@ -0,0 +1,120 @@
+//! JSON Merge Patch ([RFC 7396]) application with canonical output.
+//!
+//! [RFC 7396]: https://tools.ietf.org/html/rfc7396
+
+use std::io::Write;
+
+use serde_json::{Map, Result, Value};
+
+#[cfg(feature = "hash")]
+use crate::hash::{to_hash, Hash};
+use crate::to_writer;
+
+/// Apply the merge patch to the target, in place.
+///
+/// Follows the `MergePatch` algorithm in [RFC 7396], section 2: members of a patch object
+/// replace (or, if `null`, remove) members of the target, recursively, and any other patch
+/// replaces the target as a whole.
+///
+/// [RFC 7396]: https://tools.ietf.org/html/rfc7396#section-2
+pub fn apply_merge_patch(target: &mut Value, patch: &Value) {
+    let patch = match patch {
+        Value::Object(patch) => patch,
+        patch => {
+            *target = patch.clone();
+            return;
+        }
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    if let Value::Object(target) = target {
+        for (name, value) in patch {
+            if value.is_null() {
+                target.remove(name);
+            } else {
+                apply_merge_patch(target.entry(name.as_str()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Apply the merge patch to the target, in place, and return the result as a canonical JSON byte
+/// vector.
+///
+/// Serialization is performed as specified in [RFC 8785](https://tools.ietf.org/html/rfc8785).
+/// The target is only changed if serialization succeeds.
+///
+/// # Errors
+///
+/// Serialization can fail if the result has a number that is not valid in canonical JSON.
+pub fn merge_patch(target: &mut Value, patch: &Value) -> Result<Vec<u8>> {
+    let mut data: Vec<u8> = Vec::with_capacity(128);
+    merge_patch_to_writer(&mut data, target, patch)?;
+    Ok(data)
+}
+
+/// Apply the merge patch to the target, in place, and serialize the result as JSON into the IO
+/// stream.
+///
+/// Serialization is performed as specified in [RFC 8785](https://tools.ietf.org/html/rfc8785).
+/// The target is only changed if serialization succeeds.
+///
+/// # Errors
+///
+/// Serialization can fail if the result has a number that is not valid in canonical JSON, or if
+/// the IO stream errors.
+pub fn merge_patch_to_writer<W>(writer: W, target: &mut Value, patch: &Value) -> Result<()>
+where
+    W: Write,
+{
+    let patched = patched(target, patch);
+    to_writer(writer, &patched)?;
+    *target = patched;
+    Ok(())
+}
+
+/// A copy of the target with the merge patch applied.
+fn patched(target: &Value, patch: &Value) -> Value {
+    let mut patched = match patch {
+        Value::Object(_) => target.clone(),
+        _ => Value::Null,
+    };
+    apply_merge_patch(&mut patched, patch);
+    patched
+}
+
+/// The hashes of a target before and after a merge patch.
+#[cfg(feature = "hash")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MergePatchDigest {
+    /// The SHA-256 hash of the canonical target, before the patch.
+    pub before: Hash,
+    /// The SHA-256 hash of the canonical target, after the patch.
+    pub after: Hash,
+}
+
+#[cfg(feature = "hash")]
+impl MergePatchDigest {
+    /// Whether the patch left the canonical target unchanged.
+    pub fn is_unchanged(&self) -> bool {
+        self.before == self.after
+    }
+}
+
+/// Apply the merge patch to the target, in place, and return the hashes of the canonical target
+/// before and after.
+///
+/// The target is only changed if hashing succeeds.
+///
+/// # Errors
+///
+/// Hashing can fail if the target has a number that is not valid in canonical JSON.
+#[cfg(feature = "hash")]
+pub fn merge_patch_digest(target: &mut Value, patch: &Value) -> Result<MergePatchDigest> {
+    let before = to_hash(target)?;
+    let patched = patched(target, patch);
+    let after = to_hash(&patched)?;
+    *target = patched;
+    Ok(MergePatchDigest { before, after })
+}
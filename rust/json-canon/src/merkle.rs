@@ -0,0 +1,251 @@
+//! Merkle trees over the elements of a JSON array.
+//!
+//! Each element is serialized with [`to_writer`] and hashed as a leaf, and the tree is built as
+//! specified in [RFC 6962: Certificate Transparency], section 2.1:
+//!
+//! - the hash of a leaf is `SHA-256(0x00 || canonical element)`
+//! - the hash of a node is `SHA-256(0x01 || left || right)`
+//! - a tree of `n > 1` leaves is split so the left subtree has the largest power of two less than
+//!   `n` leaves
+//!
+//! Only the leaf hashes are kept, so a tree can be built from a stream of elements (or from a
+//! [`Read`] of a JSON array) without holding the whole array in memory. An [`InclusionProof`]
+//! proves that an element is at an index of the array, given only the root hash.
+//!
+//! [RFC 6962: Certificate Transparency]: https://tools.ietf.org/html/rfc6962
+
+use std::{fmt, io::Read};
+
+use serde::{
+    de::{self, Deserializer as _, SeqAccess, Visitor},
+    Serialize,
+};
+use serde_json::{Deserializer, Result, Value};
+use sha2::{Digest, Sha256};
+
+pub use crate::hash::Hash;
+use crate::to_writer;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Hash an element as a leaf: `SHA-256(0x00 || canonical element)`.
+///
+/// # Errors
+///
+/// Hashing can fail if `T`'s implementation of `Serialize` fails.
+pub fn leaf_hash<T>(element: &T) -> Result<Hash>
+where
+    T: Serialize + ?Sized,
+{
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    to_writer(&mut hasher, element)?;
+    Ok(hasher.finalize().into())
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// A Merkle tree over the elements of a JSON array.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MerkleTree {
+    leaves: Vec<Hash>,
+}
+
+impl MerkleTree {
+    /// Build a tree from the elements.
+    ///
+    /// # Errors
+    ///
+    /// Building can fail if `T`'s implementation of `Serialize` fails.
+    pub fn from_elements<I, T>(elements: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Serialize,
+    {
+        let leaves = elements
+            .into_iter()
+            .map(|element| leaf_hash(&element))
+            .collect::<Result<_>>()?;
+        Ok(Self { leaves })
+    }
+
+    /// Build a tree from a JSON array in the IO stream.
+    ///
+    /// Elements are parsed and hashed one at a time.
+    ///
+    /// # Errors
+    ///
+    /// Building can fail if the stream is not a JSON array.
+    pub fn from_reader<R>(reader: R) -> Result<Self>
+    where
+        R: Read,
+    {
+        let mut deserializer = Deserializer::from_reader(reader);
+        let leaves = deserializer.deserialize_seq(LeavesVisitor)?;
+        deserializer.end()?;
+        Ok(Self { leaves })
+    }
+
+    /// The number of leaves.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Whether the tree has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// The leaf hashes, in array order.
+    pub fn leaves(&self) -> &[Hash] {
+        &self.leaves
+    }
+
+    /// The root hash.
+    ///
+    /// The root hash of an empty tree is the hash of an empty string.
+    pub fn root(&self) -> Hash {
+        if self.leaves.is_empty() {
+            Sha256::digest([]).into()
+        } else {
+            subtree_root(&self.leaves)
+        }
+    }
+
+    /// Prove that the element at `index` is included in the tree.
+    ///
+    /// Returns `None` if `index` is out of bounds.
+    pub fn proof(&self, index: usize) -> Option<InclusionProof> {
+        if index >= self.leaves.len() {
+            return None;
+        }
+        let mut path = Vec::new();
+        subtree_path(&self.leaves, index, &mut path);
+        Some(InclusionProof {
+            index,
+            tree_size: self.leaves.len(),
+            path,
+        })
+    }
+}
+
+/// The largest power of two less than `n`, for `n > 1`.
+fn split(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+fn subtree_root(leaves: &[Hash]) -> Hash {
+    if leaves.len() == 1 {
+        return leaves[0];
+    }
+    let (left, right) = leaves.split_at(split(leaves.len()));
+    node_hash(&subtree_root(left), &subtree_root(right))
+}
+
+fn subtree_path(leaves: &[Hash], index: usize, path: &mut Vec<Hash>) {
+    if leaves.len() == 1 {
+        return;
+    }
+    let k = split(leaves.len());
+    let (left, right) = leaves.split_at(k);
+    if index < k {
+        subtree_path(left, index, path);
+        path.push(subtree_root(right));
+    } else {
+        subtree_path(right, index - k, path);
+        path.push(subtree_root(left));
+    }
+}
+
+/// A proof that an element is at an index of an array, as in [RFC 6962], section 2.1.1.
+///
+/// [RFC 6962]: https://tools.ietf.org/html/rfc6962#section-2.1.1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InclusionProof {
+    /// Index of the element in the array.
+    pub index: usize,
+    /// Number of elements in the array.
+    pub tree_size: usize,
+    /// Hashes of sibling subtrees, from the leaf up to the root.
+    pub path: Vec<Hash>,
+}
+
+impl InclusionProof {
+    /// Verify that `element` is included in the tree with the root hash.
+    ///
+    /// # Errors
+    ///
+    /// Verification can fail if `T`'s implementation of `Serialize` fails.
+    pub fn verify<T>(&self, element: &T, root: &Hash) -> Result<bool>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(self.verify_leaf(&leaf_hash(element)?, root))
+    }
+
+    /// Verify that the leaf hash is included in the tree with the root hash.
+    ///
+    /// Follows the verification algorithm in [RFC 9162], section 2.1.3.2.
+    ///
+    /// [RFC 9162]: https://tools.ietf.org/html/rfc9162#section-2.1.3.2
+    pub fn verify_leaf(&self, leaf: &Hash, root: &Hash) -> bool {
+        if self.index >= self.tree_size {
+            return false;
+        }
+
+        let mut fnode = self.index;
+        let mut snode = self.tree_size - 1;
+        let mut hash = *leaf;
+
+        for sibling in &self.path {
+            if snode == 0 {
+                return false;
+            }
+            if fnode & 1 == 1 || fnode == snode {
+                hash = node_hash(sibling, &hash);
+                while fnode & 1 == 0 && fnode != 0 {
+                    fnode >>= 1;
+                    snode >>= 1;
+                }
+            } else {
+                hash = node_hash(&hash, sibling);
+            }
+            fnode >>= 1;
+            snode >>= 1;
+        }
+
+        snode == 0 && &hash == root
+    }
+}
+
+struct LeavesVisitor;
+
+impl<'de> Visitor<'de> for LeavesVisitor {
+    type Value = Vec<Hash>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON array")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut leaves = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element::<Value>()? {
+            leaves.push(leaf_hash(&element).map_err(de::Error::custom)?);
+        }
+        Ok(leaves)
+    }
+}
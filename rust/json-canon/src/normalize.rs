@@ -0,0 +1,114 @@
+//! Unicode normalization of strings.
+//!
+//! RFC 8785 doesn't normalize Unicode, so `"é"` written precomposed (U+00E9) and written as `e`
+//! and a combining acute accent (U+0065 U+0301) sort, serialize, and hash differently. With a
+//! [`Normalization`], every key and string is normalized before keys are sorted and strings are
+//! written, so both are the same.
+//!
+//! The output is the RFC 8785 serialization of the normalized value. If two keys of an object are
+//! the same once normalized, serialization fails, as the object would have a duplicate key.
+//!
+//! ```rust
+//! use json_canon::normalize::{to_string, Normalization};
+//! use serde_json::json;
+//! # fn main() -> std::io::Result<()> {
+//!
+//! let precomposed = json!({ "caf\u{e9}": 1 });
+//! let decomposed = json!({ "cafe\u{301}": 1 });
+//! assert_eq!(
+//!     to_string(&precomposed, Normalization::Nfc)?,
+//!     to_string(&decomposed, Normalization::Nfc)?,
+//! );
+//! assert!(to_string(&decomposed, Normalization::StrictNfc).is_err());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    borrow::Cow,
+    io::{self, Error, ErrorKind, Write},
+};
+
+use serde::Serialize;
+use serde_json::{Result, Serializer};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+use crate::CanonicalFormatter;
+
+/// How keys and strings are normalized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    /// Normalize to Normalization Form C (canonical composition).
+    Nfc,
+    /// Normalize to Normalization Form KC (compatibility composition), which also folds
+    /// characters like `ﬁ` into `fi`.
+    Nfkc,
+    /// Don't normalize, but fail if a key or string is not in Normalization Form C.
+    StrictNfc,
+}
+
+impl Normalization {
+    /// Normalize a string fragment.
+    ///
+    /// Fragments are split at characters which JSON requires to be escaped, which are never
+    /// composed with the characters around them, so normalizing each fragment is the same as
+    /// normalizing the whole string.
+    pub(crate) fn apply(self, fragment: &str) -> io::Result<Cow<'_, str>> {
+        match self {
+            Normalization::Nfc if is_nfc(fragment) => Ok(Cow::Borrowed(fragment)),
+            Normalization::Nfc => Ok(Cow::Owned(fragment.nfc().collect())),
+            Normalization::Nfkc => Ok(Cow::Owned(fragment.nfkc().collect())),
+            Normalization::StrictNfc if is_nfc(fragment) => Ok(Cow::Borrowed(fragment)),
+            Normalization::StrictNfc => Err(Error::new(
+                ErrorKind::InvalidData,
+                "String is not in Normalization Form C.",
+            )),
+        }
+    }
+}
+
+/// Serialize the given value as JSON into the IO stream, with keys and strings normalized.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails, if two keys of an object
+/// are the same once normalized, or if a string is not normalized in strict mode.
+pub fn to_writer<W, T>(writer: W, value: &T, normalization: Normalization) -> Result<()>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    value.serialize(&mut Serializer::with_formatter(
+        writer,
+        CanonicalFormatter::new().normalization(normalization),
+    ))
+}
+
+/// Serialize the given value as a JSON byte vector, with keys and strings normalized.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails, if two keys of an object
+/// are the same once normalized, or if a string is not normalized in strict mode.
+pub fn to_vec<T>(value: &T, normalization: Normalization) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut data: Vec<u8> = Vec::with_capacity(128);
+    to_writer(&mut data, value, normalization)?;
+    Ok(data)
+}
+
+/// Serialize the given value as a String of JSON, with keys and strings normalized.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails, if two keys of an object
+/// are the same once normalized, or if a string is not normalized in strict mode.
+pub fn to_string<T>(value: &T, normalization: Normalization) -> Result<String>
+where
+    T: Serialize + ?Sized,
+{
+    let data = to_vec(value, normalization)?;
+    Ok(unsafe { String::from_utf8_unchecked(data) })
+}
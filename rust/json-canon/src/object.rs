@@ -1,9 +1,24 @@
 use std::{
+    borrow::Cow,
+    cmp::Ordering,
     io::{self, sink, Error, ErrorKind, Write},
     str::from_utf8_unchecked,
 };
 
-use serde_json::ser::{CompactFormatter, Formatter};
+use serde_json::ser::Formatter;
+
+use crate::order::KeyOrder;
+
+/// Object keys are sorted by their UTF-16 code units, as specified in RFC 8785 section 3.2.3.
+#[inline]
+pub(crate) fn cmpable_key(key: &str) -> impl Iterator<Item = impl Ord + '_> {
+    key.encode_utf16()
+}
+
+#[inline]
+pub(crate) fn cmp_keys(a: &str, b: &str) -> Ordering {
+    cmpable_key(a).cmp(cmpable_key(b))
+}
 
 #[derive(Clone, Debug)]
 pub(crate) struct ObjectEntry {
@@ -33,9 +48,8 @@ impl ObjectEntry {
     }
 
     #[inline]
-    pub(crate) fn cmpable(&self) -> impl Iterator<Item = impl Ord + '_> {
-        let key_orig = unsafe { from_utf8_unchecked(self.key_bytes.as_slice()) };
-        key_orig.encode_utf16()
+    pub(crate) fn key_orig(&self) -> &str {
+        unsafe { from_utf8_unchecked(self.key_bytes.as_slice()) }
     }
 
     #[inline]
@@ -68,17 +82,23 @@ impl ObjectEntry {
     }
 
     #[inline]
-    pub(crate) fn write_to<W>(&self, first: bool, writer: &mut W) -> io::Result<()>
+    pub(crate) fn write_to<W, F>(
+        &self,
+        first: bool,
+        writer: &mut W,
+        formatter: &mut F,
+    ) -> io::Result<()>
     where
         W: Write + ?Sized,
+        F: Formatter,
     {
-        CompactFormatter.begin_object_key(writer, first)?;
+        formatter.begin_object_key(writer, first)?;
         writer.write_all(self.key.as_slice())?;
-        CompactFormatter.end_object_key(writer)?;
+        formatter.end_object_key(writer)?;
 
-        CompactFormatter.begin_object_value(writer)?;
+        formatter.begin_object_value(writer)?;
         writer.write_all(self.value.as_slice())?;
-        CompactFormatter.end_object_value(writer)?;
+        formatter.end_object_value(writer)?;
 
         Ok(())
     }
@@ -140,81 +160,272 @@ impl Object {
         Ok(writer)
     }
 
+    /// Write the entries, sorted, and the end of the object. The start of the object was already
+    /// written by [`ObjectStack::start_object`].
+    ///
+    /// With `reject_duplicates`, errors if two keys are the same.
     #[inline]
-    pub(crate) fn write_to<W>(&mut self, writer: &mut W) -> io::Result<()>
+    pub(crate) fn write_to<W, F, K>(
+        &mut self,
+        writer: &mut W,
+        formatter: &mut F,
+        order: &K,
+        reject_duplicates: bool,
+    ) -> io::Result<()>
     where
         W: Write + ?Sized,
+        F: Formatter,
+        K: KeyOrder,
     {
-        CompactFormatter.begin_object(writer)?;
-
         let entries = &mut self.entries;
 
-        entries.sort_by(|a, b| a.cmpable().cmp(b.cmpable()));
+        entries.sort_by(|a, b| order.cmp(a.key_orig(), b.key_orig()));
+
+        // Such as two keys which are the same once normalized.
+        if reject_duplicates
+            && entries
+                .windows(2)
+                .any(|pair| pair[0].key_bytes == pair[1].key_bytes)
+        {
+            return Err(Error::new(ErrorKind::InvalidData, "Duplicate object key."));
+        }
 
         let mut first = true;
         for entry in entries {
-            entry.write_to(first, writer)?;
+            entry.write_to(first, writer, formatter)?;
+
+            first = false;
+        }
+
+        formatter.end_object(writer)?;
+
+        Ok(())
+    }
+}
+
+/// An array with set semantics, whose elements are buffered until the end of the array.
+#[derive(Clone, Debug)]
+pub(crate) struct Set {
+    elements: Vec<Vec<u8>>,
+    dedupe: bool,
+}
+
+impl Set {
+    pub(crate) fn new(dedupe: bool) -> Self {
+        Self {
+            elements: Vec::new(),
+            dedupe,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn start_element(&mut self) {
+        self.elements.push(Vec::new())
+    }
+
+    #[inline]
+    pub(crate) fn scope(&mut self) -> io::Result<impl Write + '_> {
+        self.elements.last_mut().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "Array element requested when element is not active.",
+            )
+        })
+    }
+
+    /// Write the elements, sorted by their canonical bytes, and the end of the array. The start
+    /// of the array was already written by [`ObjectStack::start_set`].
+    #[inline]
+    pub(crate) fn write_to<W, F>(&self, writer: &mut W, formatter: &mut F) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+        F: Formatter,
+    {
+        let mut elements: Vec<_> = self
+            .elements
+            .iter()
+            .map(|element| (strip_whitespace(element), element))
+            .collect();
+
+        elements.sort_by(|a, b| a.0.cmp(&b.0));
+
+        if self.dedupe {
+            elements.dedup_by(|a, b| a.0 == b.0);
+        }
+
+        let mut first = true;
+        for (_, element) in elements {
+            formatter.begin_array_value(writer, first)?;
+            writer.write_all(element)?;
+            formatter.end_array_value(writer)?;
 
             first = false;
         }
 
-        CompactFormatter.end_object(writer)?;
+        formatter.end_array(writer)?;
 
         Ok(())
     }
 }
 
+/// Remove the whitespace between tokens, which an inner formatter such as `PrettyFormatter`
+/// writes, so elements are compared by their canonical bytes.
+fn strip_whitespace(bytes: &[u8]) -> Cow<'_, [u8]> {
+    let is_whitespace = |byte: &u8| matches!(byte, b' ' | b'\t' | b'\n' | b'\r');
+    if !bytes.iter().any(is_whitespace) {
+        return Cow::Borrowed(bytes);
+    }
+    let mut stripped = Vec::with_capacity(bytes.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for byte in bytes {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if *byte == b'\\' {
+                escaped = true;
+            } else if *byte == b'"' {
+                in_string = false;
+            }
+        } else if *byte == b'"' {
+            in_string = true;
+        } else if is_whitespace(byte) {
+            continue;
+        }
+        stripped.push(*byte);
+    }
+    Cow::Owned(stripped)
+}
+
+#[derive(Clone, Debug)]
+enum Container {
+    Object(Object),
+    Set(Set),
+}
+
+/// The objects, and arrays with set semantics, which are being buffered, innermost last.
 #[derive(Clone, Debug)]
 pub(crate) struct ObjectStack {
-    objects: Vec<Object>,
+    containers: Vec<Container>,
 }
 
 impl ObjectStack {
     pub(crate) fn new() -> Self {
         Self {
-            objects: Vec::new(),
+            containers: Vec::new(),
         }
     }
 
     pub(crate) fn current_object(&mut self) -> io::Result<&mut Object> {
-        self.objects.last_mut().ok_or_else(|| {
-            Error::new(
+        match self.containers.last_mut() {
+            Some(Container::Object(object)) => Ok(object),
+            _ => Err(Error::new(
                 ErrorKind::InvalidData,
                 "Object requested when object is not active.",
-            )
-        })
+            )),
+        }
+    }
+
+    pub(crate) fn current_set(&mut self) -> io::Result<&mut Set> {
+        match self.containers.last_mut() {
+            Some(Container::Set(set)) => Ok(set),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Array requested when array is not active.",
+            )),
+        }
     }
 
     pub(crate) fn has_current_object(&mut self) -> bool {
-        !self.objects.is_empty()
+        matches!(self.containers.last(), Some(Container::Object(_)))
     }
 
+    /// Write the start of an object, then buffer its entries until the end of the object.
     #[inline]
-    pub(crate) fn start_object(&mut self) {
-        self.objects.push(Object::new())
+    pub(crate) fn start_object<W, F>(&mut self, writer: &mut W, formatter: &mut F) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+        F: Formatter,
+    {
+        formatter.begin_object(&mut self.scope(writer)?)?;
+        self.containers.push(Container::Object(Object::new()));
+        Ok(())
     }
 
     #[inline]
-    pub(crate) fn end_object<W>(&mut self, writer: &mut W) -> io::Result<()>
+    pub(crate) fn end_object<W, F, K>(
+        &mut self,
+        writer: &mut W,
+        formatter: &mut F,
+        order: &K,
+        reject_duplicates: bool,
+    ) -> io::Result<()>
     where
         W: Write + ?Sized,
+        F: Formatter,
+        K: KeyOrder,
     {
-        let mut object = self.objects.pop().ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "Object requested when object is not active.",
-            )
-        })?;
+        let mut object = match self.containers.pop() {
+            Some(Container::Object(object)) => object,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Object requested when object is not active.",
+                ))
+            }
+        };
 
-        if self.has_current_object() {
-            let mut writer = self.current_object()?.scope()?;
-            object.write_to(&mut writer)?;
-        } else {
-            object.write_to(writer)?;
-        }
+        object.write_to(
+            &mut self.scope(writer)?,
+            formatter,
+            order,
+            reject_duplicates,
+        )
+    }
+
+    /// Write the start of an array with set semantics, then buffer its elements until the end of
+    /// the array.
+    #[inline]
+    pub(crate) fn start_set<W, F>(
+        &mut self,
+        writer: &mut W,
+        formatter: &mut F,
+        dedupe: bool,
+    ) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+        F: Formatter,
+    {
+        formatter.begin_array(&mut self.scope(writer)?)?;
+        self.containers.push(Container::Set(Set::new(dedupe)));
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn start_element(&mut self) -> io::Result<()> {
+        self.current_set()?.start_element();
         Ok(())
     }
 
+    #[inline]
+    pub(crate) fn end_set<W, F>(&mut self, writer: &mut W, formatter: &mut F) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+        F: Formatter,
+    {
+        let set = match self.containers.pop() {
+            Some(Container::Set(set)) => set,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Array requested when array is not active.",
+                ))
+            }
+        };
+
+        set.write_to(&mut self.scope(writer)?, formatter)
+    }
+
     #[inline]
     pub(crate) fn start_key(&mut self) -> io::Result<()> {
         self.current_object()?.start_key();
@@ -227,6 +438,17 @@ impl ObjectStack {
         Ok(())
     }
 
+    /// The unescaped key of the current entry.
+    #[inline]
+    pub(crate) fn current_key(&mut self) -> io::Result<String> {
+        let key = self
+            .current_object()?
+            .current_entry()?
+            .key_orig()
+            .to_owned();
+        Ok(key)
+    }
+
     #[inline]
     pub(crate) fn is_in_key(&mut self) -> io::Result<bool> {
         let is_in_key = if self.has_current_object() {
@@ -241,11 +463,12 @@ impl ObjectStack {
     where
         W: Write + ?Sized,
     {
-        let writer: EitherWriter<_, _> = if self.has_current_object() {
-            let object_writer = self.current_object()?.scope()?;
-            EitherWriter::Left(object_writer)
-        } else {
-            EitherWriter::Right(writer)
+        let writer: EitherWriter<_, _> = match self.containers.last_mut() {
+            Some(Container::Object(object)) => {
+                EitherWriter::Left(EitherWriter::Left(object.scope()?))
+            }
+            Some(Container::Set(set)) => EitherWriter::Left(EitherWriter::Right(set.scope()?)),
+            None => EitherWriter::Right(writer),
         };
         Ok(writer)
     }
@@ -257,11 +480,12 @@ impl ObjectStack {
     where
         W: Write + ?Sized,
     {
-        let writer: EitherWriter<_, _> = if self.has_current_object() {
-            let object_writer = self.current_object()?.scope_with_key()?;
-            EitherWriter::Left(object_writer)
-        } else {
-            EitherWriter::Right(writer)
+        let writer: EitherWriter<_, _> = match self.containers.last_mut() {
+            Some(Container::Object(object)) => {
+                EitherWriter::Left(EitherWriter::Left(object.scope_with_key()?))
+            }
+            Some(Container::Set(set)) => EitherWriter::Left(EitherWriter::Right(set.scope()?)),
+            None => EitherWriter::Right(writer),
         };
         Ok(writer)
     }
@@ -0,0 +1,69 @@
+//! Orderings of object keys.
+//!
+//! RFC 8785 sorts keys by their UTF-16 code units ([`Utf16Order`]). Other formats sort keys by
+//! their code points ([`CodePointOrder`]), which is the same as by their UTF-8 bytes, such as
+//! Matrix's canonical JSON, DAG-JSON, and Go's `encoding/json`. The two orders only differ for
+//! keys with a code point outside the Basic Multilingual Plane, which UTF-16 writes as surrogates
+//! (U+D800 to U+DFFF), so they sort before code points from U+E000 to U+FFFF.
+//!
+//! Use another order with [`CanonicalFormatter::key_order`](crate::CanonicalFormatter::key_order).
+//! The output is then not RFC 8785.
+//!
+//! ```rust
+//! use json_canon::{order::CodePointOrder, CanonicalFormatter};
+//! use serde::Serialize;
+//! use serde_json::{json, Serializer};
+//! # fn main() -> std::io::Result<()> {
+//!
+//! let value = json!({ "\u{1f600}": 1, "\u{fffd}": 2 });
+//! assert_eq!(json_canon::to_string(&value)?, "{\"\u{1f600}\":1,\"\u{fffd}\":2}");
+//!
+//! let mut data = Vec::new();
+//! let formatter = CanonicalFormatter::new().key_order(CodePointOrder);
+//! value.serialize(&mut Serializer::with_formatter(&mut data, formatter))?;
+//! assert_eq!(data, "{\"\u{fffd}\":2,\"\u{1f600}\":1}".as_bytes());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::cmp::Ordering;
+
+use crate::object::cmp_keys;
+
+/// An ordering of object keys.
+pub trait KeyOrder {
+    /// Compare two keys, unescaped.
+    fn cmp(&self, a: &str, b: &str) -> Ordering;
+}
+
+/// Sort keys by their UTF-16 code units, as specified in RFC 8785 section 3.2.3.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Utf16Order;
+
+impl KeyOrder for Utf16Order {
+    #[inline]
+    fn cmp(&self, a: &str, b: &str) -> Ordering {
+        cmp_keys(a, b)
+    }
+}
+
+/// Sort keys by their code points, which is the same as by their UTF-8 bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CodePointOrder;
+
+impl KeyOrder for CodePointOrder {
+    #[inline]
+    fn cmp(&self, a: &str, b: &str) -> Ordering {
+        a.as_bytes().cmp(b.as_bytes())
+    }
+}
+
+impl<K> KeyOrder for &K
+where
+    K: KeyOrder + ?Sized,
+{
+    #[inline]
+    fn cmp(&self, a: &str, b: &str) -> Ordering {
+        (**self).cmp(a, b)
+    }
+}
@@ -0,0 +1,208 @@
+//! Canonicalization of a subtree of raw JSON.
+//!
+//! Some protocols sign only part of a document, such as `/payload`, inside an envelope which
+//! must otherwise stay byte-for-byte as received. [`canonicalize_at`] replaces only the bytes of
+//! the subtree at a JSON Pointer with its canonical serialization, and [`canonical_subtree`]
+//! returns only those canonical bytes.
+
+use std::io::{self, Error, ErrorKind};
+
+use serde::de::IgnoredAny;
+use serde_json::Value;
+
+use crate::{pointer, to_vec};
+
+/// Replace the subtree at the JSON Pointer with its canonical serialization, leaving every other
+/// byte of the input untouched.
+///
+/// # Errors
+///
+/// Errors if the input is not valid JSON, if there is no value at the pointer, if an object on the
+/// path to the pointer has a duplicate of the key on the path, or if the subtree has a number that
+/// is not valid in canonical JSON.
+pub fn canonicalize_at(bytes: &[u8], pointer: &str) -> io::Result<Vec<u8>> {
+    let (start, end) = locate(bytes, pointer)?;
+    let canonical = canonicalize(&bytes[start..end])?;
+    let mut output = Vec::with_capacity(bytes.len() - (end - start) + canonical.len());
+    output.extend_from_slice(&bytes[..start]);
+    output.extend_from_slice(&canonical);
+    output.extend_from_slice(&bytes[end..]);
+    Ok(output)
+}
+
+/// The canonical serialization of the subtree at the JSON Pointer.
+///
+/// # Errors
+///
+/// Errors if the input is not valid JSON, if there is no value at the pointer, if an object on the
+/// path to the pointer has a duplicate of the key on the path, or if the subtree has a number that
+/// is not valid in canonical JSON.
+pub fn canonical_subtree(bytes: &[u8], pointer: &str) -> io::Result<Vec<u8>> {
+    let (start, end) = locate(bytes, pointer)?;
+    canonicalize(&bytes[start..end])
+}
+
+fn canonicalize(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let value: Value = serde_json::from_slice(bytes)?;
+    Ok(to_vec(&value)?)
+}
+
+/// The byte range of the value at the JSON Pointer in the raw input.
+fn locate(bytes: &[u8], pointer: &str) -> io::Result<(usize, usize)> {
+    // Validate the input up front, so the scanner can assume well-formed JSON.
+    serde_json::from_slice::<IgnoredAny>(bytes)?;
+    let tokens = pointer::parse(pointer)?;
+
+    let mut scanner = Scanner { bytes, pos: 0 };
+    for token in &tokens {
+        scanner.skip_whitespace();
+        let found = match scanner.peek() {
+            Some(b'{') => scanner.find_member(token)?,
+            Some(b'[') => match pointer::parse_index(token) {
+                Some(index) => scanner.find_element(index),
+                None => false,
+            },
+            _ => false,
+        };
+        if !found {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("No value at {:?}.", pointer),
+            ));
+        }
+    }
+    scanner.skip_whitespace();
+    let start = scanner.pos;
+    scanner.skip_value();
+    Ok((start, scanner.pos))
+}
+
+/// A scanner over JSON which is known to be well-formed.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip whitespace and then the given punctuation byte, if it's next.
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_string(&mut self) {
+        self.pos += 1;
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'\\' => self.pos += 1,
+                b'"' => return,
+                _ => {}
+            }
+        }
+    }
+
+    fn skip_value(&mut self) {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'"') => self.skip_string(),
+            Some(open @ (b'{' | b'[')) => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+                if self.eat(close) {
+                    return;
+                }
+                loop {
+                    if open == b'{' {
+                        self.skip_whitespace();
+                        self.skip_string();
+                        self.eat(b':');
+                    }
+                    self.skip_value();
+                    if !self.eat(b',') {
+                        self.eat(close);
+                        return;
+                    }
+                }
+            }
+            _ => {
+                while let Some(byte) = self.peek() {
+                    if matches!(byte, b',' | b']' | b'}' | b' ' | b'\t' | b'\n' | b'\r') {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Move to the value of the member with the key, returning whether it was found.
+    ///
+    /// The rest of the object is checked for a duplicate of the key, since then which value the
+    /// pointer refers to is ambiguous.
+    fn find_member(&mut self, key: &str) -> io::Result<bool> {
+        self.pos += 1;
+        if self.eat(b'}') {
+            return Ok(false);
+        }
+        let mut found = None;
+        loop {
+            self.skip_whitespace();
+            let key_start = self.pos;
+            self.skip_string();
+            let member_key: String = serde_json::from_slice(&self.bytes[key_start..self.pos])?;
+            self.eat(b':');
+            if member_key == key {
+                if found.is_some() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Duplicate key {:?}.", key),
+                    ));
+                }
+                self.skip_whitespace();
+                found = Some(self.pos);
+            }
+            self.skip_value();
+            if !self.eat(b',') {
+                break;
+            }
+        }
+        match found {
+            Some(pos) => {
+                self.pos = pos;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Move to the element at the index, returning whether it was found.
+    fn find_element(&mut self, index: usize) -> bool {
+        self.pos += 1;
+        if self.eat(b']') {
+            return false;
+        }
+        for _ in 0..index {
+            self.skip_value();
+            if !self.eat(b',') {
+                return false;
+            }
+        }
+        true
+    }
+}
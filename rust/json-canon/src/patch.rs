@@ -0,0 +1,372 @@
+//! JSON Patch ([RFC 6902]) generation and application.
+//!
+//! [`diff`] compares two values and emits a [`Patch`] of `add`, `remove`, and `replace`
+//! operations. The patch is deterministic: object members are walked in the same order as they
+//! are serialized (by UTF-16 code units), and array elements by index, so the same two documents
+//! always result in the same patch, and the same canonical patch bytes.
+//!
+//! Values are compared by their canonical serialization, so `1.0` and `1` are equal, and applying
+//! `diff(a, b)` to `a` always results in a value with the same canonical serialization as `b`.
+//!
+//! [RFC 6902]: https://tools.ietf.org/html/rfc6902
+
+use std::io::{self, Error, ErrorKind};
+
+use serde::{
+    de::{self, DeserializeOwned},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    pointer,
+    value::{canonical_eq, zip_members},
+};
+
+/// A JSON Patch operation.
+///
+/// Serialized as an object with an `"op"` member, such as `{"op":"remove","path":"/a"}`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// A JSON Patch: a sequence of operations.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Patch(pub Vec<Operation>);
+
+const OPS: &[&str] = &["add", "remove", "replace", "move", "copy", "test"];
+
+impl Serialize for Operation {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (op, from, path, value) = match self {
+            Operation::Add { path, value } => ("add", None, path, Some(value)),
+            Operation::Remove { path } => ("remove", None, path, None),
+            Operation::Replace { path, value } => ("replace", None, path, Some(value)),
+            Operation::Move { from, path } => ("move", Some(from), path, None),
+            Operation::Copy { from, path } => ("copy", Some(from), path, None),
+            Operation::Test { path, value } => ("test", None, path, Some(value)),
+        };
+        let len = 2 + usize::from(from.is_some()) + usize::from(value.is_some());
+        let mut state = serializer.serialize_struct("Operation", len)?;
+        state.serialize_field("op", op)?;
+        if let Some(from) = from {
+            state.serialize_field("from", from)?;
+        }
+        state.serialize_field("path", path)?;
+        if let Some(value) = value {
+            state.serialize_field("value", value)?;
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Operation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut object = Map::deserialize(deserializer)?;
+        let op: String = member(&mut object, "op")?;
+        Ok(match op.as_str() {
+            "add" => Operation::Add {
+                path: member(&mut object, "path")?,
+                value: member(&mut object, "value")?,
+            },
+            "remove" => Operation::Remove {
+                path: member(&mut object, "path")?,
+            },
+            "replace" => Operation::Replace {
+                path: member(&mut object, "path")?,
+                value: member(&mut object, "value")?,
+            },
+            "move" => Operation::Move {
+                from: member(&mut object, "from")?,
+                path: member(&mut object, "path")?,
+            },
+            "copy" => Operation::Copy {
+                from: member(&mut object, "from")?,
+                path: member(&mut object, "path")?,
+            },
+            "test" => Operation::Test {
+                path: member(&mut object, "path")?,
+                value: member(&mut object, "value")?,
+            },
+            _ => return Err(de::Error::unknown_variant(&op, OPS)),
+        })
+    }
+}
+
+impl Serialize for Patch {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Patch {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::deserialize(deserializer).map(Patch)
+    }
+}
+
+/// Take a member of an operation object.
+fn member<T, E>(object: &mut Map<String, Value>, key: &'static str) -> Result<T, E>
+where
+    T: DeserializeOwned,
+    E: de::Error,
+{
+    let value = object.remove(key).ok_or_else(|| E::missing_field(key))?;
+    T::deserialize(value).map_err(E::custom)
+}
+
+impl Patch {
+    /// The operations.
+    pub fn operations(&self) -> &[Operation] {
+        &self.0
+    }
+
+    /// Whether the patch has no operations.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Apply the patch to the value.
+    ///
+    /// Either every operation is applied, or (if any operation fails) the value is unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Errors if an operation refers to a value that doesn't exist, or if a `test` operation
+    /// fails.
+    pub fn apply(&self, value: &mut Value) -> io::Result<()> {
+        let mut patched = value.clone();
+        for operation in &self.0 {
+            apply_operation(&mut patched, operation)?;
+        }
+        *value = patched;
+        Ok(())
+    }
+}
+
+/// Generate a patch which changes `a` into `b`.
+pub fn diff(a: &Value, b: &Value) -> Patch {
+    let mut operations = Vec::new();
+    diff_value(a, b, "", &mut operations);
+    Patch(operations)
+}
+
+fn diff_value(a: &Value, b: &Value, path: &str, operations: &mut Vec<Operation>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, a, b) in zip_members(a, b) {
+                let path = pointer::join(path, key);
+                match (a, b) {
+                    (Some(a), Some(b)) => diff_value(a, b, &path, operations),
+                    (Some(_), None) => operations.push(Operation::Remove { path }),
+                    (None, Some(b)) => operations.push(Operation::Add {
+                        path,
+                        value: b.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => diff_array(a, b, path, operations),
+        (a, b) => {
+            if !canonical_eq(a, b) {
+                operations.push(Operation::Replace {
+                    path: path.to_owned(),
+                    value: b.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// The most cells in the table of [`diff_array`], which takes time and memory in proportion.
+const MAX_LCS_CELLS: usize = 1 << 20;
+
+/// Diff arrays with a longest common subsequence of (canonically) equal elements.
+///
+/// Elements outside the subsequence are diffed in place where possible, or else removed or
+/// added, always in order of index. Equal elements at the start and end are skipped first, and if
+/// what's left between them is too large to compare, the whole array is replaced.
+fn diff_array(a: &[Value], b: &[Value], path: &str, operations: &mut Vec<Operation>) {
+    let prefix = a
+        .iter()
+        .zip(b)
+        .take_while(|(a, b)| canonical_eq(a, b))
+        .count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(a, b)| canonical_eq(a, b))
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+    if (a_mid.len() + 1).saturating_mul(b_mid.len() + 1) > MAX_LCS_CELLS {
+        operations.push(Operation::Replace {
+            path: path.to_owned(),
+            value: Value::Array(b.to_vec()),
+        });
+        return;
+    }
+
+    // lcs[i][j] is the length of the longest common subsequence of `a_mid[i..]` and `b_mid[j..]`
+    let mut lcs = vec![vec![0_usize; b_mid.len() + 1]; a_mid.len() + 1];
+    for i in (0..a_mid.len()).rev() {
+        for j in (0..b_mid.len()).rev() {
+            lcs[i][j] = if canonical_eq(&a_mid[i], &b_mid[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j, mut index) = (0, 0, prefix);
+    while i < a_mid.len() || j < b_mid.len() {
+        let element_path = format!("{}/{}", path, index);
+        if i < a_mid.len() && j < b_mid.len() {
+            if canonical_eq(&a_mid[i], &b_mid[j]) {
+                i += 1;
+                j += 1;
+                index += 1;
+            } else if lcs[i + 1][j + 1] == lcs[i][j] {
+                diff_value(&a_mid[i], &b_mid[j], &element_path, operations);
+                i += 1;
+                j += 1;
+                index += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                operations.push(Operation::Remove { path: element_path });
+                i += 1;
+            } else {
+                operations.push(Operation::Add {
+                    path: element_path,
+                    value: b_mid[j].clone(),
+                });
+                j += 1;
+                index += 1;
+            }
+        } else if i < a_mid.len() {
+            operations.push(Operation::Remove { path: element_path });
+            i += 1;
+        } else {
+            operations.push(Operation::Add {
+                path: element_path,
+                value: b_mid[j].clone(),
+            });
+            j += 1;
+            index += 1;
+        }
+    }
+}
+
+fn apply_operation(value: &mut Value, operation: &Operation) -> io::Result<()> {
+    match operation {
+        Operation::Add { path, value: added } => add(value, path, added.clone()),
+        Operation::Remove { path } => remove(value, path).map(|_| ()),
+        Operation::Replace {
+            path,
+            value: replacement,
+        } => {
+            let target = value.pointer_mut(path).ok_or_else(|| not_found(path))?;
+            *target = replacement.clone();
+            Ok(())
+        }
+        Operation::Move { from, path } => {
+            if from == path {
+                return Ok(());
+            }
+            if path.starts_with(from.as_str()) && path[from.len()..].starts_with('/') {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Can't move {:?} into its own child {:?}.", from, path),
+                ));
+            }
+            let moved = remove(value, from)?;
+            add(value, path, moved)
+        }
+        Operation::Copy { from, path } => {
+            let copied = value.pointer(from).ok_or_else(|| not_found(from))?.clone();
+            add(value, path, copied)
+        }
+        Operation::Test {
+            path,
+            value: expected,
+        } => {
+            let actual = value.pointer(path).ok_or_else(|| not_found(path))?;
+            if canonical_eq(actual, expected) {
+                Ok(())
+            } else {
+                Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Test failed at {:?}.", path),
+                ))
+            }
+        }
+    }
+}
+
+fn add(value: &mut Value, path: &str, added: Value) -> io::Result<()> {
+    let (parent, token) = match pointer::split_last(path)? {
+        Some(split) => split,
+        None => {
+            *value = added;
+            return Ok(());
+        }
+    };
+    match value.pointer_mut(parent) {
+        Some(Value::Object(object)) => {
+            object.insert(token.into_owned(), added);
+            Ok(())
+        }
+        Some(Value::Array(array)) => {
+            let index = if token == "-" {
+                array.len()
+            } else {
+                pointer::parse_index(&token)
+                    .filter(|index| *index <= array.len())
+                    .ok_or_else(|| not_found(path))?
+            };
+            array.insert(index, added);
+            Ok(())
+        }
+        _ => Err(not_found(path)),
+    }
+}
+
+fn remove(value: &mut Value, path: &str) -> io::Result<Value> {
+    let (parent, token) = pointer::split_last(path)?
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "The root value can't be removed."))?;
+    match value.pointer_mut(parent) {
+        Some(Value::Object(object)) => object.remove(token.as_ref()).ok_or_else(|| not_found(path)),
+        Some(Value::Array(array)) => {
+            let index = pointer::parse_index(&token)
+                .filter(|index| *index < array.len())
+                .ok_or_else(|| not_found(path))?;
+            Ok(array.remove(index))
+        }
+        _ => Err(not_found(path)),
+    }
+}
+
+fn not_found(path: &str) -> Error {
+    Error::new(ErrorKind::NotFound, format!("No value at {:?}.", path))
+}
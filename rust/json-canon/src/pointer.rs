@@ -0,0 +1,92 @@
+use std::{
+    borrow::Cow,
+    io::{self, Error, ErrorKind},
+};
+
+/// Parse a [RFC 6901] JSON Pointer into its (unescaped) reference tokens.
+///
+/// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+pub(crate) fn parse(pointer: &str) -> io::Result<Vec<Cow<'_, str>>> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(invalid(pointer));
+    }
+    pointer[1..]
+        .split('/')
+        .map(|token| unescape(token).ok_or_else(|| invalid(pointer)))
+        .collect()
+}
+
+/// Split a [RFC 6901] JSON Pointer into its parent pointer and its (unescaped) last token.
+///
+/// Returns `None` for the root pointer `""`.
+///
+/// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+pub(crate) fn split_last(pointer: &str) -> io::Result<Option<(&str, Cow<'_, str>)>> {
+    if pointer.is_empty() {
+        return Ok(None);
+    }
+    if !pointer.starts_with('/') {
+        return Err(invalid(pointer));
+    }
+    let index = pointer.rfind('/').unwrap_or(0);
+    let parent = &pointer[..index];
+    let token = unescape(&pointer[index + 1..]).ok_or_else(|| invalid(pointer))?;
+    Ok(Some((parent, token)))
+}
+
+/// Append a reference token to a JSON Pointer, escaping `~` as `~0` and `/` as `~1`.
+pub(crate) fn join(pointer: &str, token: &str) -> String {
+    let mut joined = String::with_capacity(pointer.len() + token.len() + 1);
+    joined.push_str(pointer);
+    joined.push('/');
+    for c in token.chars() {
+        match c {
+            '~' => joined.push_str("~0"),
+            '/' => joined.push_str("~1"),
+            c => joined.push(c),
+        }
+    }
+    joined
+}
+
+/// Unescape a single reference token, where `~1` is `/` and `~0` is `~`.
+pub(crate) fn unescape(token: &str) -> Option<Cow<'_, str>> {
+    if !token.contains('~') {
+        return Some(Cow::Borrowed(token));
+    }
+    let mut unescaped = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c == '~' {
+            match chars.next() {
+                Some('0') => unescaped.push('~'),
+                Some('1') => unescaped.push('/'),
+                _ => return None,
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Some(Cow::Owned(unescaped))
+}
+
+/// Parse a reference token as an array index: digits only, without leading zeros.
+pub(crate) fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty()
+        || (token.len() > 1 && token.starts_with('0'))
+        || !token.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    token.parse().ok()
+}
+
+fn invalid(pointer: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("Invalid JSON Pointer: {:?}", pointer),
+    )
+}
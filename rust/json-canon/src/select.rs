@@ -0,0 +1,236 @@
+//! Canonical selections of parts of a document.
+//!
+//! A policy like "sign `$.items[*].id` and `$.total`" covers only part of a document. [`select`]
+//! evaluates JSON Pointers ([RFC 6901]), or a subset of JSONPath ([RFC 9535]), against a value,
+//! and collects every selected value into a [`Selection`]: an object from the JSON Pointer of
+//! each selected value to the value. The canonical serialization of the selection is what both
+//! parties hash or sign, so they agree on exactly what was covered.
+//!
+//! The supported subset of JSONPath is the root `$`, followed by any of: `.name`, `['name']`,
+//! `["name"]`, `[index]`, `.*`, and `[*]`.
+//!
+//! ```rust
+//! use json_canon::select::select;
+//! use serde_json::json;
+//! # fn main() -> std::io::Result<()> {
+//!
+//! let value = json!({ "items": [{ "id": 1, "n": 2 }, { "id": 3 }], "total": 4 });
+//! let selection = select(&value, &["$.items[*].id", "/total"])?;
+//! assert_eq!(selection.to_vec()?, br#"{"/items/0/id":1,"/items/1/id":3,"/total":4}"#);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [RFC 6901]: https://tools.ietf.org/html/rfc6901
+//! [RFC 9535]: https://tools.ietf.org/html/rfc9535
+
+use std::io::{self, Error, ErrorKind, Write};
+
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+
+#[cfg(feature = "hash")]
+use crate::hash::{to_hash, Hash};
+use crate::{pointer, to_writer};
+
+/// The values selected from a document, keyed by JSON Pointer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection(Map<String, Value>);
+
+impl Selection {
+    /// The selected values, keyed by JSON Pointer.
+    pub fn values(&self) -> &Map<String, Value> {
+        &self.0
+    }
+
+    /// The selected value at the JSON Pointer, if any.
+    pub fn get(&self, pointer: &str) -> Option<&Value> {
+        self.0.get(pointer)
+    }
+
+    /// The number of selected values.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no values are selected.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Write the canonical serialization of the selection into the IO stream.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a number is not valid in canonical JSON, or if the IO stream errors.
+    pub fn to_writer<W>(&self, writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        Ok(to_writer(writer, self)?)
+    }
+
+    /// The canonical serialization of the selection.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a number is not valid in canonical JSON.
+    pub fn to_vec(&self) -> io::Result<Vec<u8>> {
+        let mut data: Vec<u8> = Vec::with_capacity(128);
+        self.to_writer(&mut data)?;
+        Ok(data)
+    }
+
+    /// The SHA-256 hash of the canonical serialization of the selection.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a number is not valid in canonical JSON.
+    #[cfg(feature = "hash")]
+    pub fn to_hash(&self) -> io::Result<Hash> {
+        Ok(to_hash(self)?)
+    }
+}
+
+impl Serialize for Selection {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Select the values at the given paths.
+///
+/// A path starting with `$` is a JSONPath, which may select any number of values. Any other path
+/// is a JSON Pointer, which must refer to a value.
+///
+/// # Errors
+///
+/// Errors if a path is not a valid JSON Pointer or supported JSONPath, or if there is no value at
+/// a JSON Pointer.
+pub fn select<S>(value: &Value, paths: &[S]) -> io::Result<Selection>
+where
+    S: AsRef<str>,
+{
+    let mut selection = Map::new();
+    for path in paths {
+        let path = path.as_ref();
+        if path.starts_with('$') {
+            let segments = parse_path(path)?;
+            select_segments(value, &segments, String::new(), &mut selection);
+        } else {
+            pointer::parse(path)?;
+            let selected = value.pointer(path).ok_or_else(|| {
+                Error::new(ErrorKind::NotFound, format!("No value at {:?}.", path))
+            })?;
+            selection.insert(path.to_owned(), selected.clone());
+        }
+    }
+    Ok(Selection(selection))
+}
+
+enum Segment {
+    Name(String),
+    Index(usize),
+    Wildcard,
+}
+
+fn select_segments(
+    value: &Value,
+    segments: &[Segment],
+    path: String,
+    selection: &mut Map<String, Value>,
+) {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => {
+            selection.insert(path, value.clone());
+            return;
+        }
+    };
+    match (segment, value) {
+        (Segment::Name(name), Value::Object(object)) => {
+            if let Some(member) = object.get(name) {
+                select_segments(member, rest, pointer::join(&path, name), selection);
+            }
+        }
+        (Segment::Index(index), Value::Array(array)) => {
+            if let Some(element) = array.get(*index) {
+                let path = format!("{}/{}", path, index);
+                select_segments(element, rest, path, selection);
+            }
+        }
+        (Segment::Wildcard, Value::Object(object)) => {
+            for (key, member) in object {
+                select_segments(member, rest, pointer::join(&path, key), selection);
+            }
+        }
+        (Segment::Wildcard, Value::Array(array)) => {
+            for (index, element) in array.iter().enumerate() {
+                let path = format!("{}/{}", path, index);
+                select_segments(element, rest, path, selection);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn parse_path(path: &str) -> io::Result<Vec<Segment>> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid JSONPath: {:?}", path),
+        )
+    };
+
+    let mut segments = Vec::new();
+    let mut rest = &path[1..];
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(|c| c == '.' || c == '[').unwrap_or(after.len());
+            let name = &after[..end];
+            segments.push(match name {
+                "" => return Err(invalid()),
+                "*" => Segment::Wildcard,
+                name => Segment::Name(name.to_owned()),
+            });
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let (segment, after) = match after.chars().next() {
+                Some(quote @ ('\'' | '"')) => {
+                    parse_quoted(&after[1..], quote).ok_or_else(invalid)?
+                }
+                _ => {
+                    let end = after.find(']').ok_or_else(invalid)?;
+                    let segment = match &after[..end] {
+                        "*" => Segment::Wildcard,
+                        index => Segment::Index(pointer::parse_index(index).ok_or_else(invalid)?),
+                    };
+                    (segment, &after[end..])
+                }
+            };
+            segments.push(segment);
+            rest = after.strip_prefix(']').ok_or_else(invalid)?;
+        } else {
+            return Err(invalid());
+        }
+    }
+    Ok(segments)
+}
+
+/// Parse a quoted name, up to and including the closing quote, where `\` escapes the next
+/// character.
+fn parse_quoted(input: &str, quote: char) -> Option<(Segment, &str)> {
+    let mut name = String::new();
+    let mut chars = input.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => name.push(chars.next()?.1),
+            c if c == quote => return Some((Segment::Name(name), &input[index + 1..])),
+            c => name.push(c),
+        }
+    }
+    None
+}
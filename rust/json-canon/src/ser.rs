@@ -1,13 +1,23 @@
 use core::num::FpCategory;
 use serde::Serialize;
 use serde_json::{
-    ser::{CharEscape, CompactFormatter, Formatter},
+    ser::{CharEscape, CompactFormatter, Formatter, PrettyFormatter},
     Result, Serializer,
 };
 
-use std::io::{self, Error, ErrorKind, Write};
+use std::{
+    borrow::Cow,
+    io::{self, Error, ErrorKind, Write},
+};
 
-use crate::object::ObjectStack;
+#[cfg(feature = "normalize")]
+use crate::normalize::Normalization;
+use crate::{
+    escape::Escaping,
+    object::ObjectStack,
+    order::{KeyOrder, Utf16Order},
+    set::{Segment, SetPaths},
+};
 
 /// Serialize the given value as a String of JSON.
 ///
@@ -51,6 +61,9 @@ where
 ///
 /// Serialization is performed as specified in [RFC 8785](https://tools.ietf.org/html/rfc8785).
 ///
+/// In debug builds, warns on stderr of an array from a collection with no deterministic order,
+/// such as a `HashSet`: see [`lint`](crate::lint).
+///
 /// # Errors
 ///
 /// Serialization can fail if `T`'s implementation of `Serialize` fails.
@@ -60,41 +73,265 @@ where
     W: Write,
     T: Serialize + ?Sized,
 {
+    #[cfg(debug_assertions)]
+    crate::lint::warn(value);
+
     value.serialize(&mut Serializer::with_formatter(
         writer,
         CanonicalFormatter::new(),
     ))
 }
 
+/// Serialize the given value as a String of pretty-printed JSON.
+///
+/// The same as [`to_string`], but indented with two spaces.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails.
+#[inline]
+pub fn to_string_pretty<T>(value: &T) -> Result<String>
+where
+    T: Serialize + ?Sized,
+{
+    let data: Vec<u8> = to_vec_pretty(value)?;
+
+    let data: String = unsafe { String::from_utf8_unchecked(data) };
+
+    Ok(data)
+}
+
+/// Serialize the given value as a pretty-printed JSON byte vector.
+///
+/// The same as [`to_vec`], but indented with two spaces.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails.
+#[inline]
+pub fn to_vec_pretty<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut data: Vec<u8> = Vec::with_capacity(128);
+
+    to_writer_pretty(&mut data, value)?;
+
+    Ok(data)
+}
+
+/// Serialize the given value as pretty-printed JSON into the IO stream.
+///
+/// The same as [`to_writer`], but indented with two spaces.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails.
+#[inline]
+pub fn to_writer_pretty<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    #[cfg(debug_assertions)]
+    crate::lint::warn(value);
+
+    value.serialize(&mut Serializer::with_formatter(
+        writer,
+        CanonicalFormatter::pretty(),
+    ))
+}
+
 static MAX_SAFE_INTEGER_U64: u64 = 9_007_199_254_740_991;
 static MAX_SAFE_INTEGER_I64: i64 = 9_007_199_254_740_991;
 static MAX_SAFE_INTEGER_U128: u128 = 9_007_199_254_740_991;
 static MAX_SAFE_INTEGER_I128: i128 = 9_007_199_254_740_991;
 
+/// A `serde_json` [`Formatter`] which writes JSON as specified in
+/// [RFC 8785](https://tools.ietf.org/html/rfc8785).
+///
+/// Use with [`serde_json::Serializer::with_formatter`], or use [`to_writer`].
+///
+/// Whitespace and punctuation are written by an inner formatter, which is [`CompactFormatter`]
+/// for canonical JSON. With [`PrettyFormatter`], the output is indented, but is otherwise the
+/// same: removing the whitespace between tokens gives the canonical JSON.
 #[derive(Clone, Debug)]
-#[repr(transparent)]
-pub struct CanonicalFormatter {
+pub struct CanonicalFormatter<F = CompactFormatter, K = Utf16Order> {
     stack: ObjectStack,
+    formatter: F,
+    escaping: Escaping,
+    /// Whether backspace and form feed are written as `\u0008` and `\u000c`.
+    unicode_controls: bool,
+    #[cfg(feature = "normalize")]
+    normalization: Option<Normalization>,
+    order: K,
+    sets: Option<SetPaths>,
+    /// The path to the value being written, only tracked with `sets`.
+    path: Vec<Segment>,
 }
 
 impl CanonicalFormatter {
     pub fn new() -> Self {
+        Self::with_formatter(CompactFormatter)
+    }
+}
+
+impl<'a> CanonicalFormatter<PrettyFormatter<'a>> {
+    /// A formatter which indents with two spaces.
+    pub fn pretty() -> Self {
+        Self::with_formatter(PrettyFormatter::new())
+    }
+}
+
+impl<F> CanonicalFormatter<F>
+where
+    F: Formatter,
+{
+    /// A formatter which writes whitespace and punctuation with the inner formatter.
+    pub fn with_formatter(formatter: F) -> Self {
         Self {
             stack: ObjectStack::new(),
+            formatter,
+            escaping: Escaping::Jcs,
+            unicode_controls: false,
+            #[cfg(feature = "normalize")]
+            normalization: None,
+            order: Utf16Order,
+            sets: None,
+            path: Vec::new(),
+        }
+    }
+}
+
+impl<F, K> CanonicalFormatter<F, K>
+where
+    F: Formatter,
+    K: KeyOrder,
+{
+    /// Escape strings with the profile. Any profile other than [`Escaping::Jcs`] is not RFC
+    /// 8785: see [`escape`](crate::escape).
+    pub fn escaping(mut self, escaping: Escaping) -> Self {
+        self.escaping = escaping;
+        self
+    }
+
+    /// Write backspace and form feed as `\u0008` and `\u000c`, rather than `\b` and `\f`, as
+    /// Go's `encoding/json` did before Go 1.22: see [`cosmos`](crate::cosmos).
+    #[cfg(feature = "cosmos")]
+    pub(crate) fn unicode_controls(mut self) -> Self {
+        self.unicode_controls = true;
+        self
+    }
+
+    /// Normalize keys and strings: see [`normalize`](crate::normalize).
+    #[cfg(feature = "normalize")]
+    pub fn normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = Some(normalization);
+        self
+    }
+
+    /// Sort object keys in the order, rather than by UTF-16 code units. Any order other than
+    /// [`Utf16Order`] is not RFC 8785: see [`order`](crate::order).
+    pub fn key_order<O>(self, order: O) -> CanonicalFormatter<F, O>
+    where
+        O: KeyOrder,
+    {
+        CanonicalFormatter {
+            stack: self.stack,
+            formatter: self.formatter,
+            escaping: self.escaping,
+            unicode_controls: self.unicode_controls,
+            #[cfg(feature = "normalize")]
+            normalization: self.normalization,
+            order,
+            sets: self.sets,
+            path: self.path,
         }
     }
+
+    /// Sort the arrays at the paths, as sets: see [`set`](crate::set).
+    pub fn sets(mut self, sets: SetPaths) -> Self {
+        self.sets = Some(sets);
+        self
+    }
 }
 
-impl Formatter for CanonicalFormatter {
+impl<F, K> CanonicalFormatter<F, K> {
+    /// Writes the escape of `"`, `\`, `/`, or a control character.
+    #[inline]
+    fn write_escape<W>(&mut self, writer: &mut W, byte: u8) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        static HEX_CHARS: [u8; 16] = *b"0123456789abcdef";
+
+        let mut writer = self.stack.scope(writer)?;
+        match byte {
+            0x08 if !self.unicode_controls => writer.write_all(b"\\b"),
+            0x09 => writer.write_all(b"\\t"),
+            0x0A => writer.write_all(b"\\n"),
+            0x0C if !self.unicode_controls => writer.write_all(b"\\f"),
+            0x0D => writer.write_all(b"\\r"),
+            b'"' => writer.write_all(b"\\\""),
+            b'/' => writer.write_all(b"\\/"),
+            b'\\' => writer.write_all(b"\\\\"),
+            control => writer.write_all(&[
+                b'\\',
+                b'u',
+                b'0',
+                b'0',
+                HEX_CHARS[(control >> 4) as usize],
+                HEX_CHARS[(control & 0xF) as usize],
+            ]),
+        }
+    }
+
+    #[cfg(feature = "normalize")]
+    #[inline]
+    fn normalize<'a>(&self, fragment: &'a str) -> io::Result<Cow<'a, str>> {
+        match self.normalization {
+            Some(normalization) => normalization.apply(fragment),
+            None => Ok(Cow::Borrowed(fragment)),
+        }
+    }
+
+    #[cfg(not(feature = "normalize"))]
+    #[inline]
+    fn normalize<'a>(&self, fragment: &'a str) -> io::Result<Cow<'a, str>> {
+        Ok(Cow::Borrowed(fragment))
+    }
+
+    #[cfg(feature = "normalize")]
+    #[inline]
+    fn is_normalizing(&self) -> bool {
+        self.normalization.is_some()
+    }
+
+    #[cfg(not(feature = "normalize"))]
+    #[inline]
+    fn is_normalizing(&self) -> bool {
+        false
+    }
+}
+
+impl Default for CanonicalFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F, K> Formatter for CanonicalFormatter<F, K>
+where
+    F: Formatter,
+    K: KeyOrder,
+{
     /// Writes a `null` value to the specified writer.
     #[inline]
     fn write_null<W>(&mut self, writer: &mut W) -> io::Result<()>
     where
         W: Write + ?Sized,
     {
-        let mut writer = self.stack.scope(writer)?;
-        writer.write_all(b"null")?;
-        Ok(())
+        self.formatter.write_null(&mut self.stack.scope(writer)?)
     }
 
     /// Writes a `true` or `false` value to the specified writer.
@@ -103,13 +340,8 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        let mut writer = self.stack.scope(writer)?;
-        if value {
-            writer.write_all(b"true")?;
-        } else {
-            writer.write_all(b"false")?;
-        }
-        Ok(())
+        self.formatter
+            .write_bool(&mut self.stack.scope(writer)?, value)
     }
 
     /// Writes an integer value like `-123` to the specified writer.
@@ -118,7 +350,8 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.write_i8(&mut self.stack.scope_with_key(writer)?, value)
+        self.formatter
+            .write_i8(&mut self.stack.scope_with_key(writer)?, value)
     }
 
     /// Writes an integer value like `-123` to the specified writer.
@@ -127,7 +360,8 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.write_i16(&mut self.stack.scope_with_key(writer)?, value)
+        self.formatter
+            .write_i16(&mut self.stack.scope_with_key(writer)?, value)
     }
 
     /// Writes an integer value like `-123` to the specified writer.
@@ -136,7 +370,8 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.write_i32(&mut self.stack.scope_with_key(writer)?, value)
+        self.formatter
+            .write_i32(&mut self.stack.scope_with_key(writer)?, value)
     }
 
     /// Writes an integer value like `-123` to the specified writer.
@@ -153,7 +388,8 @@ impl Formatter for CanonicalFormatter {
                 "i64.abs() must be less than JSON max safe integer",
             ))
         } else {
-            CompactFormatter.write_i64(&mut self.stack.scope(writer)?, value)
+            self.formatter
+                .write_i64(&mut self.stack.scope_with_key(writer)?, value)
         }
     }
 
@@ -171,7 +407,8 @@ impl Formatter for CanonicalFormatter {
                 "i128.abs() must be less than JSON max safe integer",
             ))
         } else {
-            CompactFormatter.write_i128(&mut self.stack.scope(writer)?, value)
+            self.formatter
+                .write_i128(&mut self.stack.scope_with_key(writer)?, value)
         }
     }
 
@@ -181,7 +418,8 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.write_u8(&mut self.stack.scope_with_key(writer)?, value)
+        self.formatter
+            .write_u8(&mut self.stack.scope_with_key(writer)?, value)
     }
 
     /// Writes an integer value like `123` to the specified writer.
@@ -190,7 +428,8 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.write_u16(&mut self.stack.scope_with_key(writer)?, value)
+        self.formatter
+            .write_u16(&mut self.stack.scope_with_key(writer)?, value)
     }
 
     /// Writes an integer value like `123` to the specified writer.
@@ -199,7 +438,8 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.write_u32(&mut self.stack.scope_with_key(writer)?, value)
+        self.formatter
+            .write_u32(&mut self.stack.scope_with_key(writer)?, value)
     }
 
     /// Writes an integer value like `123` to the specified writer.
@@ -216,7 +456,8 @@ impl Formatter for CanonicalFormatter {
                 "u64 must be less than JSON max safe integer",
             ))
         } else {
-            CompactFormatter.write_u64(&mut self.stack.scope(writer)?, value)
+            self.formatter
+                .write_u64(&mut self.stack.scope_with_key(writer)?, value)
         }
     }
 
@@ -234,7 +475,8 @@ impl Formatter for CanonicalFormatter {
                 "u128 must be less than JSON max safe integer",
             ))
         } else {
-            CompactFormatter.write_u128(&mut self.stack.scope(writer)?, value)
+            self.formatter
+                .write_u128(&mut self.stack.scope_with_key(writer)?, value)
         }
     }
 
@@ -292,7 +534,7 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.begin_string(&mut self.stack.scope(writer)?)
+        self.formatter.begin_string(&mut self.stack.scope(writer)?)
     }
 
     /// Called after each series of `write_string_fragment` and `write_char_escape`.
@@ -303,19 +545,36 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.end_string(&mut self.stack.scope(writer)?)
+        self.formatter.end_string(&mut self.stack.scope(writer)?)
     }
 
     /// Writes a string fragment that doesn't need any escaping to the specified writer.
+    ///
+    /// A normalized fragment can need escaping: NFKC maps U+FF02 to `"`, for example.
     #[inline]
     fn write_string_fragment<W>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()>
     where
         W: Write + ?Sized,
     {
-        let bytes = fragment.as_bytes();
-        let mut writer = self.stack.scope_with_key(writer)?;
-        writer.write_all(bytes)?;
-        Ok(())
+        let fragment = self.normalize(fragment)?;
+        // Keys are sorted by their unescaped value.
+        self.stack.key_bytes()?.write_all(fragment.as_bytes())?;
+        if let Cow::Owned(_) = fragment {
+            let mut start = 0;
+            for (index, byte) in fragment.bytes().enumerate() {
+                if byte < 0x20 || byte == b'"' || byte == b'\\' {
+                    self.escaping
+                        .write_fragment(&mut self.stack.scope(writer)?, &fragment[start..index])?;
+                    self.write_escape(writer, byte)?;
+                    start = index + 1;
+                }
+            }
+            return self
+                .escaping
+                .write_fragment(&mut self.stack.scope(writer)?, &fragment[start..]);
+        }
+        self.escaping
+            .write_fragment(&mut self.stack.scope(writer)?, &fragment)
     }
 
     /// Writes a character escape code to the specified writer.
@@ -324,54 +583,19 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        static HEX_CHARS: [u8; 16] = *b"0123456789abcdef";
-
-        match escape {
-            CharEscape::Backspace => {
-                self.stack.key_bytes()?.write_all(&[0x08])?;
-                self.stack.scope(writer)?.write_all(b"\\b")?;
-            }
-            CharEscape::Tab => {
-                self.stack.key_bytes()?.write_all(&[0x09])?;
-                self.stack.scope(writer)?.write_all(b"\\t")?;
-            }
-            CharEscape::LineFeed => {
-                self.stack.key_bytes()?.write_all(&[0x0A])?;
-                self.stack.scope(writer)?.write_all(b"\\n")?;
-            }
-            CharEscape::FormFeed => {
-                self.stack.key_bytes()?.write_all(&[0x0C])?;
-                self.stack.scope(writer)?.write_all(b"\\f")?;
-            }
-            CharEscape::CarriageReturn => {
-                self.stack.key_bytes()?.write_all(&[0x0D])?;
-                self.stack.scope(writer)?.write_all(b"\\r")?;
-            }
-            CharEscape::Quote => {
-                self.stack.key_bytes()?.write_all(&[0x22])?;
-                self.stack.scope(writer)?.write_all(b"\\\"")?;
-            }
-            CharEscape::Solidus => {
-                self.stack.key_bytes()?.write_all(&[0x2F])?;
-                self.stack.scope(writer)?.write_all(b"\\/")?;
-            }
-            CharEscape::ReverseSolidus => {
-                self.stack.key_bytes()?.write_all(&[0x5C])?;
-                self.stack.scope(writer)?.write_all(b"\\\\")?;
-            }
-            CharEscape::AsciiControl(control) => {
-                self.stack.key_bytes()?.write_all(&[control])?;
-                self.stack.scope(writer)?.write_all(&[
-                    b'\\',
-                    b'u',
-                    b'0',
-                    b'0',
-                    HEX_CHARS[(control >> 4) as usize],
-                    HEX_CHARS[(control & 0xF) as usize],
-                ])?;
-            }
-        }
-        Ok(())
+        let byte = match escape {
+            CharEscape::Quote => b'"',
+            CharEscape::ReverseSolidus => b'\\',
+            CharEscape::Solidus => b'/',
+            CharEscape::Backspace => 0x08,
+            CharEscape::FormFeed => 0x0C,
+            CharEscape::LineFeed => 0x0A,
+            CharEscape::CarriageReturn => 0x0D,
+            CharEscape::Tab => 0x09,
+            CharEscape::AsciiControl(control) => control,
+        };
+        self.stack.key_bytes()?.write_all(&[byte])?;
+        self.write_escape(writer, byte)
     }
 
     /// Called before every array.  Writes a `[` to the specified writer.
@@ -380,7 +604,18 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.begin_array(&mut self.stack.scope(writer)?)
+        let (set, dedupe) = match self.sets.as_ref().and_then(|sets| sets.matches(&self.path)) {
+            Some(dedupe) => (true, dedupe),
+            None => (false, false),
+        };
+        if self.sets.is_some() {
+            self.path.push(Segment::Index { index: 0, set });
+        }
+        if set {
+            self.stack.start_set(writer, &mut self.formatter, dedupe)
+        } else {
+            self.formatter.begin_array(&mut self.stack.scope(writer)?)
+        }
     }
 
     /// Called after every array.  Writes a `]` to the specified writer.
@@ -389,7 +624,12 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.end_array(&mut self.stack.scope(writer)?)
+        match self.path.pop() {
+            Some(Segment::Index { set: true, .. }) => {
+                self.stack.end_set(writer, &mut self.formatter)
+            }
+            _ => self.formatter.end_array(&mut self.stack.scope(writer)?),
+        }
     }
 
     /// Called before every array value.  Writes a `,` if needed to the specified writer.
@@ -398,7 +638,16 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.begin_array_value(&mut self.stack.scope(writer)?, first)
+        if let Some(Segment::Index { index, set }) = self.path.last_mut() {
+            if !first {
+                *index += 1;
+            }
+            if *set {
+                return self.stack.start_element();
+            }
+        }
+        self.formatter
+            .begin_array_value(&mut self.stack.scope(writer)?, first)
     }
 
     /// Called after every array value.
@@ -407,17 +656,23 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        CompactFormatter.end_array_value(&mut self.stack.scope(writer)?)
+        if let Some(Segment::Index { set: true, .. }) = self.path.last() {
+            return Ok(());
+        }
+        self.formatter
+            .end_array_value(&mut self.stack.scope(writer)?)
     }
 
     /// Called before every object.
     #[inline]
-    fn begin_object<W>(&mut self, _writer: &mut W) -> io::Result<()>
+    fn begin_object<W>(&mut self, writer: &mut W) -> io::Result<()>
     where
         W: Write + ?Sized,
     {
-        self.stack.start_object();
-        Ok(())
+        if self.sets.is_some() {
+            self.path.push(Segment::Key(String::new()));
+        }
+        self.stack.start_object(writer, &mut self.formatter)
     }
 
     /// Called after every object.
@@ -426,7 +681,13 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        self.stack.end_object(writer)
+        if self.sets.is_some() {
+            self.path.pop();
+        }
+        // Normalization can make two keys the same.
+        let reject_duplicates = self.is_normalizing();
+        self.stack
+            .end_object(writer, &mut self.formatter, &self.order, reject_duplicates)
     }
 
     /// Called before every object key.
@@ -444,7 +705,14 @@ impl Formatter for CanonicalFormatter {
     where
         W: Write + ?Sized,
     {
-        self.stack.end_key()
+        self.stack.end_key()?;
+        if self.sets.is_some() {
+            let key = self.stack.current_key()?;
+            if let Some(segment) = self.path.last_mut() {
+                *segment = Segment::Key(key);
+            }
+        }
+        Ok(())
     }
 
     /// Called before every object value.
@@ -0,0 +1,182 @@
+//! Arrays with set semantics.
+//!
+//! RFC 8785 keeps the order of array elements, so two documents with the same tags in a
+//! different order have different canonical forms. [`SetPaths`] declares which arrays are sets:
+//! their elements are sorted by their canonical serialization, and optionally deduplicated, before
+//! the array is written. Deduplication is declared per path, with [`SetPaths::dedupe_at`], or for
+//! every path, with [`SetPaths::dedupe`].
+//!
+//! Paths are JSON Pointers, where a `*` reference token matches any one key or index, such as
+//! `/users/*/roles`. The output is the RFC 8785 serialization of the value with its sets sorted.
+//!
+//! ```rust
+//! use json_canon::set::{to_string, SetPaths};
+//! use serde_json::json;
+//! # fn main() -> std::io::Result<()> {
+//!
+//! let value = json!({
+//!     "tags": ["b", "a", "b"],
+//!     "users": [{ "roles": [{ "id": 2 }, { "id": 1 }] }],
+//!     "steps": [2, 1],
+//! });
+//! let sets = SetPaths::new(&["/users/*/roles"])?.dedupe_at("/tags")?;
+//! assert_eq!(
+//!     to_string(&value, &sets)?,
+//!     r#"{"steps":[2,1],"tags":["a","b"],"users":[{"roles":[{"id":1},{"id":2}]}]}"#
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+use serde_json::{Result, Serializer};
+
+use crate::{pointer, CanonicalFormatter};
+
+/// The paths of the arrays with set semantics.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SetPaths {
+    patterns: Vec<Pattern>,
+    dedupe: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Pattern {
+    tokens: Vec<String>,
+    dedupe: bool,
+}
+
+impl Pattern {
+    fn parse(pattern: &str, dedupe: bool) -> io::Result<Self> {
+        let tokens = pointer::parse(pattern)?;
+        Ok(Self {
+            tokens: tokens.into_iter().map(|token| token.into_owned()).collect(),
+            dedupe,
+        })
+    }
+
+    fn matches(&self, path: &[Segment]) -> bool {
+        self.tokens.len() == path.len()
+            && self
+                .tokens
+                .iter()
+                .zip(path)
+                .all(|(token, segment)| token == "*" || segment.matches(token))
+    }
+}
+
+impl SetPaths {
+    /// Arrays at the JSON Pointers, where a `*` reference token matches any one key or index.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a pattern is not a valid JSON Pointer.
+    pub fn new<P>(patterns: &[P]) -> io::Result<Self>
+    where
+        P: AsRef<str>,
+    {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| Pattern::parse(pattern.as_ref(), false))
+            .collect::<io::Result<_>>()?;
+        Ok(Self {
+            patterns,
+            dedupe: false,
+        })
+    }
+
+    /// Also the arrays at the JSON Pointer, removing elements with the same canonical
+    /// serialization.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the pattern is not a valid JSON Pointer.
+    pub fn dedupe_at<P>(mut self, pattern: P) -> io::Result<Self>
+    where
+        P: AsRef<str>,
+    {
+        self.patterns.push(Pattern::parse(pattern.as_ref(), true)?);
+        Ok(self)
+    }
+
+    /// Whether to remove elements with the same canonical serialization from the arrays at every
+    /// path, not only those from [`SetPaths::dedupe_at`].
+    pub fn dedupe(mut self, dedupe: bool) -> Self {
+        self.dedupe = dedupe;
+        self
+    }
+
+    /// Whether the array at the path is a set, and if so, whether to dedupe it.
+    pub(crate) fn matches(&self, path: &[Segment]) -> Option<bool> {
+        let mut matching = self
+            .patterns
+            .iter()
+            .filter(|pattern| pattern.matches(path))
+            .peekable();
+        matching.peek()?;
+        Some(self.dedupe || matching.any(|pattern| pattern.dedupe))
+    }
+}
+
+/// A reference token of the path to the value being written.
+#[derive(Clone, Debug)]
+pub(crate) enum Segment {
+    /// An object member, with its unescaped key.
+    Key(String),
+    /// An array element, and whether the array is a set.
+    Index { index: usize, set: bool },
+}
+
+impl Segment {
+    fn matches(&self, token: &str) -> bool {
+        match self {
+            Segment::Key(key) => key == token,
+            Segment::Index { index, .. } => pointer::parse_index(token) == Some(*index),
+        }
+    }
+}
+
+/// Serialize the given value as JSON into the IO stream, with the arrays at the paths sorted.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails.
+pub fn to_writer<W, T>(writer: W, value: &T, sets: &SetPaths) -> Result<()>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    value.serialize(&mut Serializer::with_formatter(
+        writer,
+        CanonicalFormatter::new().sets(sets.clone()),
+    ))
+}
+
+/// Serialize the given value as a JSON byte vector, with the arrays at the paths sorted.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails.
+pub fn to_vec<T>(value: &T, sets: &SetPaths) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut data: Vec<u8> = Vec::with_capacity(128);
+    to_writer(&mut data, value, sets)?;
+    Ok(data)
+}
+
+/// Serialize the given value as a String of JSON, with the arrays at the paths sorted.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` fails.
+pub fn to_string<T>(value: &T, sets: &SetPaths) -> Result<String>
+where
+    T: Serialize + ?Sized,
+{
+    let data = to_vec(value, sets)?;
+    Ok(unsafe { String::from_utf8_unchecked(data) })
+}
@@ -0,0 +1,300 @@
+//! Sorted serialization of collections with no deterministic order.
+//!
+//! A `HashSet` is serialized as an array in its iteration order, which changes from run to run,
+//! so its canonical serialization does too. [`CanonicalSet`] serializes any collection as an
+//! array sorted by the canonical serialization of each element, and [`seq`] does the same as a
+//! `#[serde(with = ...)]` adapter.
+//!
+//! [`CanonicalFormatter`] already sorts the keys of objects, so a `HashMap` has the same canonical
+//! serialization in any order. [`CanonicalMap`] and [`map`] sort the entries of a map by their
+//! keys, in the same order as the canonical formatter (by the UTF-16 code units of each unescaped
+//! key), for a serializer which doesn't sort keys, such as `serde_json::to_vec`.
+//!
+//! In debug builds, [`to_writer`](crate::to_writer) warns of a sequence from a collection with no
+//! deterministic order: see [`lint`](crate::lint).
+//!
+//! ```rust
+//! use std::collections::HashSet;
+//!
+//! use json_canon::{sorted::CanonicalSet, to_string};
+//! use serde_derive::Serialize;
+//! # fn main() -> std::io::Result<()> {
+//!
+//! #[derive(Serialize)]
+//! struct User {
+//!     #[serde(with = "json_canon::sorted::seq")]
+//!     roles: HashSet<String>,
+//!     keys: CanonicalSet<HashSet<u32>>,
+//! }
+//!
+//! let user = User {
+//!     roles: ["write", "admin", "read"].iter().map(|role| role.to_string()).collect(),
+//!     keys: CanonicalSet([10, 2, 1].iter().copied().collect()),
+//! };
+//! assert_eq!(
+//!     to_string(&user)?,
+//!     r#"{"keys":[1,10,2],"roles":["admin","read","write"]}"#
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ops::{Deref, DerefMut};
+
+use serde::{
+    ser::{Error, SerializeMap},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use serde_json::Value;
+
+use crate::{object::cmp_keys, CanonicalFormatter};
+
+/// A collection, serialized as an array sorted by the canonical serialization of each element.
+///
+/// Elements with the same canonical serialization are all kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CanonicalSet<T>(pub T);
+
+/// A map, serialized with its entries sorted by key, as RFC 8785 sorts the members of an object.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CanonicalMap<T>(pub T);
+
+impl<T> Serialize for CanonicalSet<T>
+where
+    for<'a> &'a T: IntoIterator,
+    for<'a> <&'a T as IntoIterator>::Item: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        seq::serialize(&self.0, serializer)
+    }
+}
+
+impl<T, K, V> Serialize for CanonicalMap<T>
+where
+    for<'a> &'a T: IntoIterator<Item = (&'a K, &'a V)>,
+    K: Serialize,
+    V: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        map::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for CanonicalSet<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        seq::deserialize(deserializer).map(CanonicalSet)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for CanonicalMap<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        map::deserialize(deserializer).map(CanonicalMap)
+    }
+}
+
+impl<T> Deref for CanonicalSet<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CanonicalSet<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for CanonicalSet<T> {
+    fn from(collection: T) -> Self {
+        Self(collection)
+    }
+}
+
+impl<T> Deref for CanonicalMap<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CanonicalMap<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for CanonicalMap<T> {
+    fn from(map: T) -> Self {
+        Self(map)
+    }
+}
+
+/// Pair each item with the canonical serialization of its sort key, sorted by it.
+fn sort_by_canonical<I, F, T, E>(items: I, key: F) -> Result<Vec<I::Item>, E>
+where
+    I: IntoIterator,
+    F: Fn(&I::Item) -> &T,
+    T: Serialize + ?Sized,
+    E: Error,
+{
+    let mut items = items
+        .into_iter()
+        .map(|item| Ok((canonical(key(&item)).map_err(E::custom)?, item)))
+        .collect::<Result<Vec<_>, E>>()?;
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(items.into_iter().map(|(_, item)| item).collect())
+}
+
+/// The canonical serialization of a value.
+///
+/// Not [`to_vec`](crate::to_vec), which would warn again of a collection in the value that the
+/// whole value was already checked for.
+fn canonical<T>(value: &T) -> serde_json::Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut bytes = Vec::with_capacity(128);
+    value.serialize(&mut serde_json::Serializer::with_formatter(
+        &mut bytes,
+        CanonicalFormatter::new(),
+    ))?;
+    Ok(bytes)
+}
+
+/// The entries of a map, sorted by key, as RFC 8785 sorts the members of an object.
+fn sort_by_key<'a, I, K, V, E>(entries: I) -> Result<Vec<(&'a K, &'a V)>, E>
+where
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+    K: Serialize + ?Sized + 'a,
+    V: ?Sized + 'a,
+    E: Error,
+{
+    let mut entries = entries
+        .into_iter()
+        .map(|(key, value)| Ok((key_string(key).map_err(E::custom)?, (key, value))))
+        .collect::<Result<Vec<_>, E>>()?;
+    entries.sort_by(|a, b| cmp_keys(&a.0, &b.0));
+    Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+}
+
+/// The key as a JSON object key: unescaped, with a number or boolean key as a string.
+fn key_string<K>(key: &K) -> serde_json::Result<String>
+where
+    K: Serialize + ?Sized,
+{
+    struct Entry<'a, K: ?Sized>(&'a K);
+
+    impl<'a, K> Serialize for Entry<'a, K>
+    where
+        K: Serialize + ?Sized,
+    {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut map = serializer.serialize_map(Some(1))?;
+            map.serialize_entry(self.0, &())?;
+            map.end()
+        }
+    }
+
+    match serde_json::to_value(Entry(key))? {
+        Value::Object(object) => Ok(object.into_iter().next().unwrap_or_default().0),
+        _ => Err(serde_json::Error::custom("Key is not a string.")),
+    }
+}
+
+/// Serialize a collection as an array sorted by the canonical serialization of each element.
+///
+/// Use with `#[serde(with = "json_canon::sorted::seq")]`.
+pub mod seq {
+    use super::*;
+
+    /// # Errors
+    ///
+    /// Errors if an element fails to serialize, or has a number that is not valid in canonical
+    /// JSON.
+    pub fn serialize<'a, T, S>(collection: &'a T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        &'a T: IntoIterator,
+        <&'a T as IntoIterator>::Item: Serialize,
+        S: Serializer,
+    {
+        let elements = sort_by_canonical(collection, |element| element)?;
+        serializer.collect_seq(elements)
+    }
+
+    /// Deserialize the collection as usual.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the collection fails to deserialize.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Serialize a map with its entries sorted by key, as RFC 8785 sorts the members of an object.
+///
+/// Use with `#[serde(with = "json_canon::sorted::map")]`.
+pub mod map {
+    use super::*;
+
+    /// # Errors
+    ///
+    /// Errors if a key or value fails to serialize, or if a key has a number that is not valid in
+    /// canonical JSON.
+    pub fn serialize<'a, T, K, V, S>(map: &'a T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        &'a T: IntoIterator<Item = (&'a K, &'a V)>,
+        K: Serialize + 'a,
+        V: Serialize + 'a,
+        S: Serializer,
+    {
+        let entries = sort_by_key(map)?;
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (key, value) in entries {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+
+    /// Deserialize the map as usual.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the map fails to deserialize.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
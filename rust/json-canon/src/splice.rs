@@ -0,0 +1,110 @@
+//! Serialization of arrays and objects whose children are already serialized.
+//!
+//! The [`store`](crate::store) and [`document`](crate::document) keep the canonical serialization
+//! of each subtree, and build a parent from its children's bytes rather than serializing the whole
+//! subtree again. The parent is still written by a [`Formatter`], usually a
+//! [`CanonicalFormatter`](crate::CanonicalFormatter), which escapes and sorts the keys: only the
+//! children's bytes are spliced in, as raw fragments.
+
+use std::{
+    io::{self, Error, ErrorKind, Write},
+    str,
+};
+
+use serde::Serialize;
+use serde_json::{
+    ser::{CharEscape, Formatter},
+    Serializer,
+};
+
+/// Serialize an array of serialized elements with the formatter.
+///
+/// # Errors
+///
+/// Errors if an element is not UTF-8, or if the formatter errors.
+pub(crate) fn array<'a, F, I>(mut formatter: F, elements: I) -> io::Result<Vec<u8>>
+where
+    F: Formatter,
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    let mut bytes = Vec::with_capacity(128);
+    formatter.begin_array(&mut bytes)?;
+    for (index, element) in elements.into_iter().enumerate() {
+        formatter.begin_array_value(&mut bytes, index == 0)?;
+        formatter.write_raw_fragment(&mut bytes, fragment(element)?)?;
+        formatter.end_array_value(&mut bytes)?;
+    }
+    formatter.end_array(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Serialize an object of keys and serialized members with the formatter.
+///
+/// # Errors
+///
+/// Errors if a member is not UTF-8, or if the formatter errors.
+pub(crate) fn object<'a, F, I>(mut formatter: F, members: I) -> io::Result<Vec<u8>>
+where
+    F: Formatter,
+    I: IntoIterator<Item = (&'a str, &'a [u8])>,
+{
+    let mut bytes = Vec::with_capacity(128);
+    formatter.begin_object(&mut bytes)?;
+    for (index, (key, member)) in members.into_iter().enumerate() {
+        formatter.begin_object_key(&mut bytes, index == 0)?;
+        key.serialize(&mut Serializer::with_formatter(
+            &mut bytes,
+            Strings(&mut formatter),
+        ))?;
+        formatter.end_object_key(&mut bytes)?;
+        formatter.begin_object_value(&mut bytes)?;
+        formatter.write_raw_fragment(&mut bytes, fragment(member)?)?;
+        formatter.end_object_value(&mut bytes)?;
+    }
+    formatter.end_object(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn fragment(bytes: &[u8]) -> io::Result<&str> {
+    str::from_utf8(bytes).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+}
+
+/// Forwards the string methods to a formatter, so a key is escaped (and normalized) by it.
+struct Strings<'a, F>(&'a mut F);
+
+impl<'a, F> Formatter for Strings<'a, F>
+where
+    F: Formatter,
+{
+    #[inline]
+    fn begin_string<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        self.0.begin_string(writer)
+    }
+
+    #[inline]
+    fn end_string<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        self.0.end_string(writer)
+    }
+
+    #[inline]
+    fn write_string_fragment<W>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        self.0.write_string_fragment(writer, fragment)
+    }
+
+    #[inline]
+    fn write_char_escape<W>(&mut self, writer: &mut W, escape: CharEscape) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        self.0.write_char_escape(writer, escape)
+    }
+}
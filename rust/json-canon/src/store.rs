@@ -0,0 +1,383 @@
+//! Content-addressed storage of JSON subtrees.
+//!
+//! A [`Store`] breaks a [`Value`] into its arrays and objects, and stores each distinct subtree
+//! once, keyed by the [`Hash`](type@Hash) of its canonical serialization. A document with
+//! many identical subtrees (or many documents sharing subtrees) is only stored once per subtree.
+//!
+//! Each subtree is stored as a node, which is itself canonical JSON:
+//!
+//! - an array is `{"array":[child,...]}`
+//! - an object is `{"object":[[key,child],...]}`, with members in canonical order
+//! - a root `null`, boolean, number, or string is `{"value":value}`
+//!
+//! where each child is either a `null`, boolean, number, or string, or a link `{"link":hash}` to
+//! another node, with the hash in hexadecimal.
+//!
+//! Nodes are stored in a [`Backend`]: either a [`MemoryBackend`] or a [`FsBackend`].
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Error, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use serde_json::{from_slice, json, Value};
+
+use crate::{
+    hash::{from_hex, hash_bytes, to_hex, Hash},
+    object::cmp_keys,
+    splice, to_vec, CanonicalFormatter,
+};
+
+/// Storage of nodes by hash.
+pub trait Backend {
+    /// Get the node with the hash, if stored.
+    fn get(&self, hash: &Hash) -> io::Result<Option<Vec<u8>>>;
+
+    /// Store the node with the hash, if not already stored.
+    fn put(&mut self, hash: &Hash, node: &[u8]) -> io::Result<()>;
+
+    /// Whether the node with the hash is stored.
+    fn contains(&self, hash: &Hash) -> io::Result<bool> {
+        Ok(self.get(hash)?.is_some())
+    }
+}
+
+/// Storage of nodes in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryBackend {
+    nodes: HashMap<Hash, Vec<u8>>,
+}
+
+impl MemoryBackend {
+    /// Store nodes in memory, starting with none.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of stored nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether no nodes are stored.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn get(&self, hash: &Hash) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.nodes.get(hash).cloned())
+    }
+
+    fn put(&mut self, hash: &Hash, node: &[u8]) -> io::Result<()> {
+        self.nodes.entry(*hash).or_insert_with(|| node.to_vec());
+        Ok(())
+    }
+
+    fn contains(&self, hash: &Hash) -> io::Result<bool> {
+        Ok(self.nodes.contains_key(hash))
+    }
+}
+
+/// Storage of nodes as files in a directory.
+///
+/// Each node is a file named by its hash in hexadecimal, in a subdirectory named by the first two
+/// hexadecimal digits. Files are written to a temporary path, unique to the write, and renamed into
+/// place, so a node is never partially written. Nodes are checked against their hash when read by
+/// the [`Store`], not by the backend.
+#[derive(Clone, Debug)]
+pub struct FsBackend {
+    root: PathBuf,
+}
+
+impl FsBackend {
+    /// Store nodes in the directory, which is created if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the directory can't be created.
+    pub fn new<P>(root: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    fn path(&self, hash: &Hash) -> PathBuf {
+        let hex = to_hex(hash);
+        self.root.join(&hex[..2]).join(&hex[2..])
+    }
+}
+
+/// The number of temporary files written by this process, to name each uniquely.
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+impl Backend for FsBackend {
+    fn get(&self, hash: &Hash) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(hash)) {
+            Ok(node) => Ok(Some(node)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn put(&mut self, hash: &Hash, node: &[u8]) -> io::Result<()> {
+        let path = self.path(hash);
+        if path.exists() {
+            return Ok(());
+        }
+        let dir = path.parent().unwrap_or(&self.root);
+        fs::create_dir_all(dir)?;
+        let tmp_path = path.with_extension(format!(
+            "{}-{}.tmp",
+            process::id(),
+            TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&tmp_path, node)?;
+        fs::rename(&tmp_path, &path)
+    }
+
+    fn contains(&self, hash: &Hash) -> io::Result<bool> {
+        Ok(self.path(hash).exists())
+    }
+}
+
+/// A content-addressed store of JSON subtrees.
+#[derive(Clone, Debug, Default)]
+pub struct Store<B> {
+    backend: B,
+}
+
+impl<B> Store<B>
+where
+    B: Backend,
+{
+    /// Store values as nodes in the backend.
+    pub fn new(backend: B) -> Self {
+        Self { backend }
+    }
+
+    /// The backend.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Store the value, returning the hash of its canonical serialization.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a number is not valid in canonical JSON, or if the backend errors.
+    pub fn put(&mut self, value: &Value) -> io::Result<Hash> {
+        let (child, bytes) = self.put_child(value)?;
+        let hash = hash_bytes(&bytes);
+        if link(&child)?.is_none() {
+            self.backend
+                .put(&hash, &to_vec(&json!({ "value": child }))?)?;
+        }
+        Ok(hash)
+    }
+
+    /// Store an array or object node, returning the child referring to it, and the canonical
+    /// serialization of the value.
+    fn put_child(&mut self, value: &Value) -> io::Result<(Value, Vec<u8>)> {
+        let (node, bytes) = match value {
+            Value::Array(array) => {
+                let mut children = Vec::with_capacity(array.len());
+                let mut elements = Vec::with_capacity(array.len());
+                for element in array {
+                    let (child, element_bytes) = self.put_child(element)?;
+                    children.push(child);
+                    elements.push(element_bytes);
+                }
+                let bytes = splice::array(
+                    CanonicalFormatter::new(),
+                    elements.iter().map(Vec::as_slice),
+                )?;
+                (json!({ "array": children }), bytes)
+            }
+            Value::Object(object) => {
+                let mut members: Vec<(&String, &Value)> = object.iter().collect();
+                members.sort_by(|(a, _), (b, _)| cmp_keys(a, b));
+
+                let mut children = Vec::with_capacity(members.len());
+                let mut member_bytes = Vec::with_capacity(members.len());
+                for (key, member) in members {
+                    let (child, bytes) = self.put_child(member)?;
+                    children.push(json!([key, child]));
+                    member_bytes.push((key.as_str(), bytes));
+                }
+                let bytes = splice::object(
+                    CanonicalFormatter::new(),
+                    member_bytes
+                        .iter()
+                        .map(|(key, bytes)| (*key, bytes.as_slice())),
+                )?;
+                (json!({ "object": children }), bytes)
+            }
+            value => return Ok((value.clone(), to_vec(value)?)),
+        };
+
+        let hash = hash_bytes(&bytes);
+        self.backend.put(&hash, &to_vec(&node)?)?;
+        Ok((json!({ "link": to_hex(&hash) }), bytes))
+    }
+
+    /// Whether the value with the hash is stored.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the backend errors.
+    pub fn contains(&self, hash: &Hash) -> io::Result<bool> {
+        self.backend.contains(hash)
+    }
+
+    /// Rebuild the value with the hash.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value or any of its subtrees is not stored, or doesn't match its hash, or if
+    /// the backend errors.
+    pub fn get(&self, hash: &Hash) -> io::Result<Value> {
+        Ok(from_slice(&self.to_vec(hash)?)?)
+    }
+
+    /// Write the canonical serialization of the value with the hash into the IO stream.
+    ///
+    /// The output is the same as [`to_writer`](crate::to_writer) of the value. Nothing is written unless every
+    /// subtree matches its hash.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value or any of its subtrees is not stored, or doesn't match its hash, or if
+    /// the backend or the IO stream errors.
+    pub fn write_to<W>(&self, hash: &Hash, writer: &mut W) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        writer.write_all(&self.to_vec(hash)?)
+    }
+
+    /// The canonical serialization of the value with the hash.
+    ///
+    /// Each subtree is hashed again as it's rebuilt, so a corrupted or tampered node is an error
+    /// rather than a different value.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the value or any of its subtrees is not stored, or doesn't match its hash, or if
+    /// the backend errors.
+    pub fn to_vec(&self, hash: &Hash) -> io::Result<Vec<u8>> {
+        let bytes = match self.get_node(hash)? {
+            Node::Value(value) => to_vec(&value)?,
+            Node::Array(children) => {
+                let elements = children
+                    .iter()
+                    .map(|child| self.child_to_vec(child))
+                    .collect::<io::Result<Vec<_>>>()?;
+                splice::array(
+                    CanonicalFormatter::new(),
+                    elements.iter().map(Vec::as_slice),
+                )?
+            }
+            Node::Object(members) => {
+                let member_bytes = members
+                    .iter()
+                    .map(|(key, child)| Ok((key.as_str(), self.child_to_vec(child)?)))
+                    .collect::<io::Result<Vec<_>>>()?;
+                splice::object(
+                    CanonicalFormatter::new(),
+                    member_bytes
+                        .iter()
+                        .map(|(key, bytes)| (*key, bytes.as_slice())),
+                )?
+            }
+        };
+        if hash_bytes(&bytes) != *hash {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Node {} does not match its hash.", to_hex(hash)),
+            ));
+        }
+        Ok(bytes)
+    }
+
+    fn child_to_vec(&self, child: &Value) -> io::Result<Vec<u8>> {
+        match link(child)? {
+            Some(hash) => self.to_vec(&hash),
+            None => Ok(to_vec(child)?),
+        }
+    }
+
+    fn get_node(&self, hash: &Hash) -> io::Result<Node> {
+        let node = self.backend.get(hash)?.ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("Node {} is not stored.", to_hex(hash)),
+            )
+        })?;
+        Node::from_slice(&node)
+    }
+}
+
+enum Node {
+    Value(Value),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Node {
+    fn from_slice(node: &[u8]) -> io::Result<Self> {
+        let mut node = match from_slice(node)? {
+            Value::Object(node) if node.len() == 1 => node,
+            _ => return Err(invalid_node()),
+        };
+        if let Some(value) = node.remove("value") {
+            return Ok(Node::Value(value));
+        }
+        if let Some(Value::Array(children)) = node.remove("array") {
+            return Ok(Node::Array(children));
+        }
+        if let Some(Value::Array(members)) = node.remove("object") {
+            return members
+                .into_iter()
+                .map(|member| match member {
+                    Value::Array(mut pair) if pair.len() == 2 => {
+                        let child = pair.pop().unwrap_or_default();
+                        match pair.pop() {
+                            Some(Value::String(key)) => Ok((key, child)),
+                            _ => Err(invalid_node()),
+                        }
+                    }
+                    _ => Err(invalid_node()),
+                })
+                .collect::<io::Result<_>>()
+                .map(Node::Object);
+        }
+        Err(invalid_node())
+    }
+}
+
+/// The hash of a link child, or `None` for a `null`, boolean, number, or string child.
+fn link(child: &Value) -> io::Result<Option<Hash>> {
+    match child {
+        Value::Object(object) => match object.get("link") {
+            Some(Value::String(hex)) if object.len() == 1 => {
+                from_hex(hex).map(Some).ok_or_else(invalid_node)
+            }
+            _ => Err(invalid_node()),
+        },
+        Value::Array(_) => Err(invalid_node()),
+        _ => Ok(None),
+    }
+}
+
+fn invalid_node() -> Error {
+    Error::new(ErrorKind::InvalidData, "Stored node is not valid.")
+}
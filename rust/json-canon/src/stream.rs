@@ -0,0 +1,291 @@
+//! Canonicalization of streams of JSON texts.
+//!
+//! Supports [JSON Lines] (each text followed by LF) and JSON Text Sequences ([RFC 7464]: each
+//! text preceded by RS and followed by LF). Texts are read and written one record at a time, so
+//! memory is bounded by the size of the largest record, which can itself be limited with
+//! [`StreamOptions::max_record_len`].
+//!
+//! [JSON Lines]: https://jsonlines.org
+//! [RFC 7464]: https://tools.ietf.org/html/rfc7464
+
+use std::{
+    error,
+    fmt::{self, Display},
+    io::{self, BufRead, Error, ErrorKind, Read, Write},
+};
+
+use serde_json::Value;
+
+use crate::to_writer;
+
+/// The record separator that begins each text in a JSON Text Sequence.
+pub const RS: u8 = 0x1E;
+
+/// How records are framed in a stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// JSON Lines: each text is followed by LF.
+    Lines,
+    /// JSON Text Sequences (RFC 7464): each text is preceded by RS and followed by LF.
+    Sequence,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Framing::Lines
+    }
+}
+
+/// What to do when a record is not valid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnError {
+    /// Report the record, and carry on with the next record.
+    Skip,
+    /// Stop the stream with an error.
+    Abort,
+}
+
+impl Default for OnError {
+    fn default() -> Self {
+        OnError::Skip
+    }
+}
+
+/// Options for [`canonicalize_stream`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StreamOptions {
+    /// How records are framed in the input.
+    pub input: Framing,
+    /// How records are framed in the output.
+    pub output: Framing,
+    /// What to do when a record is not valid.
+    pub on_error: OnError,
+    /// The maximum length of a record in bytes, if any. Longer records are not valid.
+    pub max_record_len: Option<usize>,
+}
+
+/// A record that is not valid.
+#[derive(Debug)]
+pub struct RecordError {
+    /// The index of the record in the stream, counting from 0, and not counting blank records.
+    pub index: usize,
+    /// Why the record is not valid.
+    pub error: Error,
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Record {}: {}", self.index, self.error)
+    }
+}
+
+impl error::Error for RecordError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<RecordError> for Error {
+    fn from(error: RecordError) -> Self {
+        Error::new(error.error.kind(), error)
+    }
+}
+
+/// The outcome of [`canonicalize_stream`].
+#[derive(Debug, Default)]
+pub struct StreamReport {
+    /// The number of records written.
+    pub records: usize,
+    /// The records that were not valid, and so were skipped.
+    pub errors: Vec<RecordError>,
+}
+
+/// An iterator over the records of a stream, parsed as JSON.
+///
+/// Each item is an error if the stream can't be read, or else the record, or why the record is
+/// not valid.
+#[derive(Debug)]
+pub struct Records<R> {
+    reader: R,
+    framing: Framing,
+    max_record_len: Option<usize>,
+    index: usize,
+    buffer: Vec<u8>,
+}
+
+impl<R> Records<R>
+where
+    R: BufRead,
+{
+    pub fn new(reader: R, framing: Framing) -> Self {
+        Self {
+            reader,
+            framing,
+            max_record_len: None,
+            index: 0,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Limit the length of a record in bytes. Longer records are not valid.
+    pub fn max_record_len(mut self, max_record_len: Option<usize>) -> Self {
+        self.max_record_len = max_record_len;
+        self
+    }
+
+    fn delimiter(&self) -> u8 {
+        match self.framing {
+            Framing::Lines => b'\n',
+            Framing::Sequence => RS,
+        }
+    }
+
+    /// Read the next record into the buffer, returning whether it fit within the maximum length,
+    /// or `None` at the end of the stream.
+    fn read_record(&mut self) -> io::Result<Option<bool>> {
+        let delimiter = self.delimiter();
+        self.buffer.clear();
+        let read = match self.max_record_len {
+            None => self.reader.read_until(delimiter, &mut self.buffer)?,
+            Some(max) => {
+                let limit = max.saturating_add(1) as u64;
+                let read = (&mut self.reader)
+                    .take(limit)
+                    .read_until(delimiter, &mut self.buffer)?;
+                if self.buffer.last() != Some(&delimiter) && read as u64 == limit {
+                    skip_until(&mut self.reader, delimiter)?;
+                    return Ok(Some(false));
+                }
+                read
+            }
+        };
+        if read == 0 {
+            return Ok(None);
+        }
+        if self.buffer.last() == Some(&delimiter) {
+            self.buffer.pop();
+        }
+        Ok(Some(true))
+    }
+}
+
+impl<R> Iterator for Records<R>
+where
+    R: BufRead,
+{
+    type Item = io::Result<Result<Value, RecordError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let fits = match self.read_record() {
+                Ok(Some(fits)) => fits,
+                Ok(None) => return None,
+                Err(error) => return Some(Err(error)),
+            };
+            if fits && self.buffer.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            let index = self.index;
+            self.index += 1;
+            let record = if fits {
+                serde_json::from_slice(&self.buffer).map_err(Error::from)
+            } else {
+                Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Record is longer than {} bytes.",
+                        self.max_record_len.unwrap_or_default()
+                    ),
+                ))
+            };
+            return Some(Ok(record.map_err(|error| RecordError { index, error })));
+        }
+    }
+}
+
+fn skip_until<R>(reader: &mut R, delimiter: u8) -> io::Result<()>
+where
+    R: BufRead,
+{
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        match buffer.iter().position(|byte| *byte == delimiter) {
+            Some(position) => {
+                reader.consume(position + 1);
+                return Ok(());
+            }
+            None => {
+                let len = buffer.len();
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+/// Write a value as a canonical record into the IO stream.
+///
+/// # Errors
+///
+/// Errors if a number is not valid in canonical JSON, or if the IO stream errors.
+pub fn write_record<W>(mut writer: W, value: &Value, framing: Framing) -> io::Result<()>
+where
+    W: Write,
+{
+    if framing == Framing::Sequence {
+        writer.write_all(&[RS])?;
+    }
+    to_writer(&mut writer, value)?;
+    writer.write_all(b"\n")
+}
+
+/// Read a stream of JSON texts, and write each one canonicalized.
+///
+/// Records which are not valid JSON, or which can't be canonicalized, are either skipped and
+/// reported, or abort the stream, depending on [`StreamOptions::on_error`].
+///
+/// # Errors
+///
+/// Errors if either IO stream errors, or if a record is not valid and the stream is set to abort.
+pub fn canonicalize_stream<R, W>(
+    reader: R,
+    mut writer: W,
+    options: StreamOptions,
+) -> io::Result<StreamReport>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut report = StreamReport::default();
+    let records = Records::new(reader, options.input).max_record_len(options.max_record_len);
+    for record in records {
+        let error = match record? {
+            Ok(value) => {
+                // Serialize to a buffer first, so a record that can't be canonicalized is not
+                // partially written.
+                let mut data = Vec::with_capacity(128);
+                match write_record(&mut data, &value, options.output) {
+                    Ok(()) => {
+                        writer.write_all(&data)?;
+                        report.records += 1;
+                        continue;
+                    }
+                    Err(error) => RecordError {
+                        index: report.records + report.errors.len(),
+                        error,
+                    },
+                }
+            }
+            Err(error) => error,
+        };
+        match options.on_error {
+            OnError::Skip => report.errors.push(error),
+            OnError::Abort => return Err(error.into()),
+        }
+    }
+    writer.flush()?;
+    Ok(report)
+}
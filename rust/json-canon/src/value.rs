@@ -0,0 +1,70 @@
+use std::cmp::Ordering;
+
+use serde_json::{Map, Value};
+
+use crate::{object::cmp_keys, to_vec};
+
+/// Whether the values have the same canonical serialization.
+///
+/// Unlike `==`, numbers are equal if they serialize the same, so `1.0` equals `1`.
+pub(crate) fn canonical_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => match (to_vec(a), to_vec(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => a == b,
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| canonical_eq(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).map_or(false, |b| canonical_eq(a, b)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+/// The members of the object, in canonical order.
+pub(crate) fn sorted_members(object: &Map<String, Value>) -> Vec<(&String, &Value)> {
+    let mut members: Vec<(&String, &Value)> = object.iter().collect();
+    members.sort_by(|(a, _), (b, _)| cmp_keys(a, b));
+    members
+}
+
+/// The union of the members of two objects, in canonical order.
+pub(crate) fn zip_members<'a>(
+    a: &'a Map<String, Value>,
+    b: &'a Map<String, Value>,
+) -> Vec<(&'a String, Option<&'a Value>, Option<&'a Value>)> {
+    let a = sorted_members(a);
+    let b = sorted_members(b);
+    let mut members = Vec::with_capacity(a.len().max(b.len()));
+    let (mut a, mut b) = (a.into_iter().peekable(), b.into_iter().peekable());
+    loop {
+        let order = match (a.peek(), b.peek()) {
+            (Some((a_key, _)), Some((b_key, _))) => cmp_keys(a_key, b_key),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        match order {
+            Ordering::Less => {
+                if let Some((key, a)) = a.next() {
+                    members.push((key, Some(a), None));
+                }
+            }
+            Ordering::Greater => {
+                if let Some((key, b)) = b.next() {
+                    members.push((key, None, Some(b)));
+                }
+            }
+            Ordering::Equal => {
+                if let (Some((key, a)), Some((_, b))) = (a.next(), b.next()) {
+                    members.push((key, Some(a), Some(b)));
+                }
+            }
+        }
+    }
+    members
+}
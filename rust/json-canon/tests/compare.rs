@@ -0,0 +1,119 @@
+use std::io::{self, ErrorKind};
+
+use json_canon::compare::{compare, compare_datasets, Change, RecordChange};
+use serde_json::{from_str, json, Value};
+
+#[test]
+fn test_compare() -> io::Result<()> {
+    let a: Value = from_str(r#"{"a": 1.0, "b": [1, 2, 3], "c": {"d": "e"}, "\ufb01": 1}"#)?;
+    let b: Value =
+        from_str(r#"{"\ud83d\ude00": 1, "a": 1, "b": [1, 20], "c": {"d": "f", "g": null}}"#)?;
+    assert_eq!(
+        compare(&a, &b),
+        vec![
+            Change::Changed {
+                path: "/b/1".into(),
+                from: json!(2),
+                to: json!(20)
+            },
+            Change::Removed {
+                path: "/b/2".into(),
+                value: json!(3)
+            },
+            Change::Changed {
+                path: "/c/d".into(),
+                from: json!("e"),
+                to: json!("f")
+            },
+            Change::Added {
+                path: "/c/g".into(),
+                value: json!(null)
+            },
+            Change::Added {
+                path: "/\u{1f600}".into(),
+                value: json!(1)
+            },
+            Change::Removed {
+                path: "/\u{fb01}".into(),
+                value: json!(1)
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_compare_types() -> io::Result<()> {
+    assert_eq!(
+        compare(&json!({ "a": [1] }), &json!({ "a": { "0": 1 } })),
+        vec![Change::Changed {
+            path: "/a".into(),
+            from: json!([1]),
+            to: json!({ "0": 1 })
+        }]
+    );
+    let a: Value = from_str(r#"[1e2, {"x": 0.5}]"#)?;
+    let b: Value = from_str(r#"[100, {"x": 5e-1}]"#)?;
+    assert!(compare(&a, &b).is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_compare_datasets() -> io::Result<()> {
+    let a = b"{\"id\": \"b\", \"n\": 1.0}\n{\"id\": \"a\", \"n\": 1}\n{\"id\": 3}\n";
+    let b = b"{\"n\": 1, \"id\": \"b\"}\n{\"id\": \"c\"}\n\n{\"id\": \"a\", \"n\": 2}\n";
+    assert_eq!(
+        compare_datasets(&a[..], &b[..], "/id")?,
+        vec![
+            RecordChange {
+                key: r#""a""#.into(),
+                change: Change::Changed {
+                    path: "/n".into(),
+                    from: json!(1),
+                    to: json!(2)
+                },
+            },
+            RecordChange {
+                key: r#""c""#.into(),
+                change: Change::Added {
+                    path: "".into(),
+                    value: json!({ "id": "c" })
+                },
+            },
+            RecordChange {
+                key: "3".into(),
+                change: Change::Removed {
+                    path: "".into(),
+                    value: json!({ "id": 3 })
+                },
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_compare_datasets_in_key_order() -> io::Result<()> {
+    // By UTF-8 bytes, U+E000 would come before U+1F600.
+    let a = "{\"id\": \"\u{e000}\"}\n{\"id\": \"\u{1f600}\"}\n";
+    let keys: Vec<String> = compare_datasets(a.as_bytes(), &b""[..], "/id")?
+        .into_iter()
+        .map(|change| change.key)
+        .collect();
+    assert_eq!(keys, vec!["\"\u{1f600}\"", "\"\u{e000}\""]);
+    Ok(())
+}
+
+#[test]
+fn test_compare_datasets_errors() {
+    let valid = &b"{\"id\": 1}\n"[..];
+    for (a, key) in [
+        (&b"{\"id\": 1}\n{\"id\": 1.0}\n"[..], "/id"),
+        (&b"{\"id\": 1}\n{\"no\": 1}\n"[..], "/id"),
+        (&b"{\"id\": 1}\nnot json\n"[..], "/id"),
+    ] {
+        let error = compare_datasets(a, valid, key).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", error);
+    }
+    assert!(compare_datasets(valid, valid, "id").is_err());
+}
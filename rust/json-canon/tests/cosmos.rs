@@ -0,0 +1,84 @@
+#![cfg(feature = "cosmos")]
+
+use std::io;
+
+use json_canon::cosmos::{sort_json, to_vec, Coin, StdFee, StdSignDoc};
+use serde_json::json;
+
+const ADDR: &str = "cosmos1w3jhxap3ta047h6lta047h6lta047h6l4ufpyg";
+
+fn sign_doc(timeout_height: u64) -> StdSignDoc<Vec<&'static str>> {
+    StdSignDoc {
+        account_number: 3,
+        chain_id: "1234".to_owned(),
+        fee: StdFee::new(vec![Coin::new(150, "atom")], 100_000),
+        memo: "memo".to_owned(),
+        // `testdata.TestMsg` is amino JSON of its signers.
+        msgs: vec![vec![ADDR]],
+        sequence: 6,
+        timeout_height,
+    }
+}
+
+// From `TestStdSignBytes` in the Cosmos SDK's `x/auth/migrations/legacytx`.
+#[test]
+fn test_std_sign_bytes() -> io::Result<()> {
+    assert_eq!(
+        String::from_utf8(sign_doc(0).sign_bytes()?).unwrap(),
+        format!("{{\"account_number\":\"3\",\"chain_id\":\"1234\",\"fee\":{{\"amount\":[{{\"amount\":\"150\",\"denom\":\"atom\"}}],\"gas\":\"100000\"}},\"memo\":\"memo\",\"msgs\":[[\"{}\"]],\"sequence\":\"6\"}}", ADDR)
+    );
+    assert_eq!(
+        String::from_utf8(sign_doc(10).sign_bytes()?).unwrap(),
+        format!("{{\"account_number\":\"3\",\"chain_id\":\"1234\",\"fee\":{{\"amount\":[{{\"amount\":\"150\",\"denom\":\"atom\"}}],\"gas\":\"100000\"}},\"memo\":\"memo\",\"msgs\":[[\"{}\"]],\"sequence\":\"6\",\"timeout_height\":\"10\"}}", ADDR)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_empty_fee() -> io::Result<()> {
+    let mut doc = sign_doc(0);
+    doc.fee = StdFee {
+        payer: "cosmos1payer".to_owned(),
+        ..StdFee::new(Vec::new(), 0)
+    };
+    let bytes = String::from_utf8(doc.sign_bytes()?).unwrap();
+    assert!(bytes.contains(r#""fee":{"amount":[],"gas":"0","payer":"cosmos1payer"}"#));
+    Ok(())
+}
+
+// From `TestSortJSON` in the Cosmos SDK's `types`.
+#[test]
+fn test_sort_json() -> io::Result<()> {
+    assert_eq!(
+        sort_json(br#"{"cosmos":"foo", "atom":"bar",  "tendermint":"foobar"}"#)?,
+        br#"{"atom":"bar","cosmos":"foo","tendermint":"foobar"}"#
+    );
+    assert_eq!(
+        sort_json(br#"{"chain_id":"test-chain-1","sequence":1,"fee_bytes":{"amount":[{"amount":5,"denom":"photon"}],"gas":10000},"msg_bytes":{"inputs":[{"address":"696E707574","coins":[{"amount":10,"denom":"atom"}]}],"outputs":[{"address":"6F7574707574","coins":[{"amount":10,"denom":"atom"}]}]},"alt_bytes":null}"#)?,
+        br#"{"alt_bytes":null,"chain_id":"test-chain-1","fee_bytes":{"amount":[{"amount":5,"denom":"photon"}],"gas":10000},"msg_bytes":{"inputs":[{"address":"696E707574","coins":[{"amount":10,"denom":"atom"}]}],"outputs":[{"address":"6F7574707574","coins":[{"amount":10,"denom":"atom"}]}]},"sequence":1}"#
+    );
+    assert!(sort_json(br#""cosmos":"foo",,,, "atom":"bar",  "tendermint":"foobar"}"#).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_go_encoding() -> io::Result<()> {
+    // Go sorts keys by UTF-8 bytes, where RFC 8785 sorts U+1F600 (as surrogates) before U+E000.
+    let value = json!({ "\u{1f600}": 1, "\u{e000}": 2, "a": 3 });
+    assert_eq!(
+        String::from_utf8(to_vec(&value)?).unwrap(),
+        "{\"a\":3,\"\u{e000}\":2,\"\u{1f600}\":1}"
+    );
+    assert_eq!(
+        json_canon::to_string(&value)?,
+        "{\"a\":3,\"\u{1f600}\":1,\"\u{e000}\":2}"
+    );
+
+    // As Go's `json.Marshal` escapes strings.
+    let value = json!("<a href=\"x&y\">\u{8}\u{c}\n\t\u{1}\u{2028}\u{2029}/\u{7f}\u{e9}</a>");
+    assert_eq!(
+        String::from_utf8(to_vec(&value)?).unwrap(),
+        "\"\\u003ca href=\\\"x\\u0026y\\\"\\u003e\\u0008\\u000c\\n\\t\\u0001\\u2028\\u2029/\u{7f}\u{e9}\\u003c/a\\u003e\""
+    );
+    Ok(())
+}
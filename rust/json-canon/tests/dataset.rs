@@ -0,0 +1,110 @@
+use std::{env, fs, io};
+
+use json_canon::dataset::{canonicalize_dataset, DatasetOptions, DatasetReport, Dedupe};
+
+const DATASET: &[u8] = b"{\"id\": 2, \"tags\": [\"b\"]}
+{\"tags\": [\"b\"], \"id\": 2.0}
+[1e0]
+
+{\"id\": 1}
+not json
+[1]
+{\"id\": 10}
+";
+
+#[track_caller]
+fn test_dataset(options: &DatasetOptions, expected: &str) -> io::Result<DatasetReport> {
+    let mut output = Vec::new();
+    let report = canonicalize_dataset(DATASET, &mut output, options)?;
+    assert_eq!(String::from_utf8(output).unwrap(), expected);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].index, 4);
+    Ok(report)
+}
+
+#[test]
+fn test_canonicalize() -> io::Result<()> {
+    let report = test_dataset(
+        &DatasetOptions::default(),
+        "{\"id\":2,\"tags\":[\"b\"]}\n{\"id\":2,\"tags\":[\"b\"]}\n[1]\n{\"id\":1}\n[1]\n{\"id\":10}\n",
+    )?;
+    assert_eq!((report.records, report.duplicates), (6, 0));
+    Ok(())
+}
+
+#[test]
+fn test_dedupe() -> io::Result<()> {
+    #[allow(unused_mut)]
+    let mut dedupes = vec![Dedupe::Bytes];
+    #[cfg(feature = "hash")]
+    dedupes.push(Dedupe::Hash);
+    for dedupe in dedupes {
+        let options = DatasetOptions {
+            dedupe: Some(dedupe),
+            ..DatasetOptions::default()
+        };
+        let report = test_dataset(
+            &options,
+            "{\"id\":2,\"tags\":[\"b\"]}\n[1]\n{\"id\":1}\n{\"id\":10}\n",
+        )?;
+        assert_eq!((report.records, report.duplicates), (4, 2));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_sort() -> io::Result<()> {
+    let options = DatasetOptions {
+        sort: true,
+        ..DatasetOptions::default()
+    };
+    test_dataset(
+        &options,
+        "[1]\n[1]\n{\"id\":10}\n{\"id\":1}\n{\"id\":2,\"tags\":[\"b\"]}\n{\"id\":2,\"tags\":[\"b\"]}\n",
+    )?;
+
+    let options = DatasetOptions {
+        sort: true,
+        dedupe: Some(Dedupe::Bytes),
+        ..DatasetOptions::default()
+    };
+    let report = test_dataset(
+        &options,
+        "[1]\n{\"id\":10}\n{\"id\":1}\n{\"id\":2,\"tags\":[\"b\"]}\n",
+    )?;
+    assert_eq!((report.records, report.duplicates), (4, 2));
+    Ok(())
+}
+
+#[test]
+fn test_external_sort() -> io::Result<()> {
+    let temp_dir = env::temp_dir().join(format!("json-canon-dataset-{}", std::process::id()));
+    fs::create_dir_all(&temp_dir)?;
+
+    let input: String = (0..1000)
+        .map(|i| format!("{{\"n\": {}, \"m\": {}.0}}\n", (i * 7919) % 500, i % 3))
+        .collect();
+    let in_memory = DatasetOptions {
+        sort: true,
+        dedupe: Some(Dedupe::Bytes),
+        ..DatasetOptions::default()
+    };
+    let external = DatasetOptions {
+        max_memory: 256,
+        temp_dir: Some(temp_dir.clone()),
+        ..in_memory.clone()
+    };
+
+    let mut expected = Vec::new();
+    let expected_report = canonicalize_dataset(input.as_bytes(), &mut expected, &in_memory)?;
+    let mut actual = Vec::new();
+    let actual_report = canonicalize_dataset(input.as_bytes(), &mut actual, &external)?;
+    assert_eq!(actual, expected);
+    assert_eq!(actual_report.records, expected_report.records);
+    assert_eq!(actual_report.duplicates, expected_report.duplicates);
+    assert_eq!(actual_report.records + actual_report.duplicates, 1000);
+
+    // Temporary files are removed.
+    assert_eq!(fs::read_dir(&temp_dir)?.count(), 0);
+    fs::remove_dir(&temp_dir)
+}
@@ -0,0 +1,194 @@
+#![cfg(feature = "disclosure")]
+
+use std::io;
+
+use json_canon::{
+    disclosure::{conceal, conceal_with_salt, disclose, Disclosure},
+    to_string,
+};
+use serde_json::{json, Value};
+
+fn credential() -> Value {
+    json!({
+        "given_name": "John",
+        "family_name": "Doe",
+        "address": {
+            "street_address": "123 Main St",
+            "country": "US"
+        },
+        "nationalities": ["US", "DE"]
+    })
+}
+
+#[test]
+fn test_disclosure_is_canonical() -> io::Result<()> {
+    let disclosure = Disclosure::new(
+        "2GLC42sKQveCfGfryNRN9w".into(),
+        Some("family_name".into()),
+        json!("Möbius"),
+    )?;
+    // base64url of `["2GLC42sKQveCfGfryNRN9w","family_name","Möbius"]`
+    assert_eq!(
+        disclosure.encoded(),
+        "WyIyR0xDNDJzS1F2ZUNmR2ZyeU5STjl3IiwiZmFtaWx5X25hbWUiLCJNw7ZiaXVzIl0"
+    );
+    assert_eq!(Disclosure::from_encoded(disclosure.encoded())?, disclosure);
+
+    let element = Disclosure::new(
+        "lklxF5jMYlGTPUovMNIvCA".into(),
+        None,
+        json!({ "b": 1, "a": 2 }),
+    )?;
+    // base64url of `["lklxF5jMYlGTPUovMNIvCA",{"a":2,"b":1}]`
+    assert_eq!(
+        element.encoded(),
+        "WyJsa2x4RjVqTVlsR1RQVW92TU5JdkNBIix7ImEiOjIsImIiOjF9XQ"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_disclosure_rejects_non_canonical() {
+    // base64url of `["salt", "family_name", "Doe"]`, with whitespace
+    let encoded = "WyJzYWx0IiwgImZhbWlseV9uYW1lIiwgIkRvZSJd";
+    let err = Disclosure::from_encoded(encoded).unwrap_err();
+    assert_eq!(err.to_string(), "Disclosure is not in canonical form.");
+}
+
+#[test]
+fn test_conceal_and_disclose() -> io::Result<()> {
+    let mut value = credential();
+    let given_name = conceal_with_salt(&mut value, "/given_name", "salt-1".into())?;
+    let street = conceal_with_salt(&mut value, "/address/street_address", "salt-2".into())?;
+    let nationality = conceal_with_salt(&mut value, "/nationalities/1", "salt-3".into())?;
+
+    assert_eq!(value.get("given_name"), None);
+    assert_eq!(
+        value["_sd"],
+        json!([given_name.digest()]),
+        "object members are replaced by digests"
+    );
+    assert_eq!(value["address"]["_sd"], json!([street.digest()]));
+    assert_eq!(
+        value["nationalities"],
+        json!(["US", { "...": nationality.digest() }])
+    );
+
+    let all = disclose(&value, &[given_name.clone(), street, nationality])?;
+    assert_eq!(all, credential());
+
+    let some = disclose(&value, &[given_name])?;
+    assert_eq!(
+        some,
+        json!({
+            "given_name": "John",
+            "family_name": "Doe",
+            "address": { "country": "US" },
+            "nationalities": ["US"]
+        })
+    );
+
+    let none = disclose(&value, &[])?;
+    assert_eq!(
+        none,
+        json!({
+            "family_name": "Doe",
+            "address": { "country": "US" },
+            "nationalities": ["US"]
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_conceal_sets_hash_algorithm() -> io::Result<()> {
+    let mut value = credential();
+    let street = conceal_with_salt(&mut value, "/address/street_address", "salt-1".into())?;
+    assert_eq!(value["_sd_alg"], json!("sha-256"));
+    assert_eq!(value["address"].get("_sd_alg"), None);
+
+    let encoded = to_string(&value)?;
+    let decoded: Value = serde_json::from_str(&encoded)?;
+    assert_eq!(disclose(&decoded, &[street])?, credential());
+
+    let mut other = credential();
+    other["_sd_alg"] = json!("sha-512");
+    assert_eq!(
+        conceal(&mut other, "/given_name").unwrap_err().to_string(),
+        "Hash algorithm must be \"sha-256\"."
+    );
+    assert!(conceal(&mut value, "/_sd_alg").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_conceal_nested() -> io::Result<()> {
+    let mut value = credential();
+    let street = conceal(&mut value, "/address/street_address")?;
+    let address = conceal(&mut value, "/address")?;
+
+    assert_eq!(disclose(&value, &[address.clone(), street])?, credential());
+    assert_eq!(
+        disclose(&value, &[address])?["address"],
+        json!({ "country": "US" })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_random_salts_differ() -> io::Result<()> {
+    let a = conceal(&mut credential(), "/given_name")?;
+    let b = conceal(&mut credential(), "/given_name")?;
+    assert_ne!(a.salt(), b.salt());
+    assert_ne!(a.digest(), b.digest());
+    Ok(())
+}
+
+#[test]
+fn test_disclose_errors() -> io::Result<()> {
+    let mut value = credential();
+    let given_name = conceal_with_salt(&mut value, "/given_name", "salt-1".into())?;
+
+    let unreferenced = Disclosure::new("salt-2".into(), Some("age".into()), json!(42))?;
+    assert_eq!(
+        disclose(&value, &[given_name.clone(), unreferenced])
+            .unwrap_err()
+            .to_string(),
+        "Disclosure is not referenced by any digest."
+    );
+
+    assert_eq!(
+        disclose(&value, &[given_name.clone(), given_name.clone()])
+            .unwrap_err()
+            .to_string(),
+        "Disclosure was given more than once."
+    );
+
+    let mut nested = value.clone();
+    nested["address"]["_sd_alg"] = json!("sha-256");
+    assert_eq!(
+        disclose(&nested, std::slice::from_ref(&given_name))
+            .unwrap_err()
+            .to_string(),
+        "Hash algorithm must only be given at the top level."
+    );
+
+    let mut tampered = value.clone();
+    tampered["given_name"] = json!("Jane");
+    assert_eq!(
+        disclose(&tampered, &[given_name]).unwrap_err().to_string(),
+        "Disclosed member \"given_name\" already exists."
+    );
+    Ok(())
+}
+
+#[test]
+fn test_conceal_errors() {
+    let mut value = credential();
+    assert!(conceal(&mut value, "").is_err());
+    assert!(conceal(&mut value, "/missing").is_err());
+    assert!(conceal(&mut value, "/nationalities/2").is_err());
+    assert!(conceal(&mut value, "/nationalities/01").is_err());
+    assert!(conceal(&mut value, "/given_name/0").is_err());
+    assert_eq!(value, credential());
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "hash")]
+
+use std::io;
+
+use json_canon::{document::CanonicalDocument, hash::to_hash, to_vec};
+use serde_json::{from_str, json, Value};
+
+#[track_caller]
+fn assert_canonical(document: &mut CanonicalDocument) -> io::Result<()> {
+    let value = document.to_value();
+    assert_eq!(document.to_vec()?, to_vec(&value)?);
+    assert_eq!(document.hash()?, to_hash(&value)?);
+    Ok(())
+}
+
+#[test]
+fn test_matches_to_vec() -> io::Result<()> {
+    for input in [
+        include_str!("../../../test-data/input/arrays.json"),
+        include_str!("../../../test-data/input/french.json"),
+        include_str!("../../../test-data/input/structures.json"),
+        include_str!("../../../test-data/input/unicode.json"),
+        include_str!("../../../test-data/input/values.json"),
+        include_str!("../../../test-data/input/weird.json"),
+    ] {
+        let value: Value = from_str(input)?;
+        let mut document = CanonicalDocument::new(value.clone());
+        assert_eq!(document.to_vec()?, to_vec(&value)?);
+        assert_eq!(document.hash()?, to_hash(&value)?);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_edits() -> io::Result<()> {
+    let mut document = CanonicalDocument::new(json!({
+        "items": [{ "id": 1, "qty": 2 }, { "id": 2, "qty": 5 }],
+        "total": 7,
+        "meta": { "created": "2023-05-01", "tags": ["a", "b"] }
+    }));
+    assert_canonical(&mut document)?;
+
+    assert_eq!(document.set("/items/1/qty", json!(6))?, Some(json!(5)));
+    assert_eq!(document.set("/total", json!(8))?, Some(json!(7)));
+    assert_canonical(&mut document)?;
+
+    assert_eq!(document.set("/meta/updated", json!("2023-05-02"))?, None);
+    assert_eq!(document.set("/meta/\u{fb01}", json!(1))?, None);
+    assert_eq!(document.set("/meta/\u{1f600}", json!(2))?, None);
+    assert_eq!(document.set("/items/-", json!({ "id": 3 }))?, None);
+    assert_canonical(&mut document)?;
+
+    assert_eq!(document.remove("/meta/tags/0")?, json!("a"));
+    assert_eq!(document.remove("/items/0")?, json!({ "id": 1, "qty": 2 }));
+    assert_canonical(&mut document)?;
+
+    assert_eq!(
+        document.to_value(),
+        json!({
+            "items": [{ "id": 2, "qty": 6 }, { "id": 3 }],
+            "total": 8,
+            "meta": {
+                "created": "2023-05-01",
+                "tags": ["b"],
+                "updated": "2023-05-02",
+                "\u{fb01}": 1,
+                "\u{1f600}": 2
+            }
+        })
+    );
+    assert_eq!(
+        document.to_vec()?,
+        r#"{"items":[{"id":2,"qty":6},{"id":3}],"meta":{"created":"2023-05-01","tags":["b"],"updated":"2023-05-02","😀":2,"ﬁ":1},"total":8}"#.as_bytes()
+    );
+
+    assert_eq!(document.set("", json!([1]))?.map(|_| ()), Some(()));
+    assert_eq!(document.to_vec()?, b"[1]");
+    Ok(())
+}
+
+#[test]
+fn test_get_and_hash_at() -> io::Result<()> {
+    let mut document = CanonicalDocument::new(json!({ "a/b": { "~c": [1, 2] } }));
+    assert_eq!(document.get("/a~1b/~0c/1"), Some(json!(2)));
+    assert_eq!(document.get("/a~1b/~0c/2"), None);
+    assert_eq!(document.get("/missing"), None);
+    assert_eq!(document.hash_at("/a~1b/~0c")?, to_hash(&json!([1, 2]))?);
+
+    document.set("/a~1b/~0c/0", json!(3))?;
+    assert_eq!(document.hash_at("/a~1b/~0c")?, to_hash(&json!([3, 2]))?);
+    assert_canonical(&mut document)?;
+    Ok(())
+}
+
+#[test]
+fn test_errors() -> io::Result<()> {
+    let mut document = CanonicalDocument::new(json!({ "a": [1], "b": null }));
+    assert!(document.set("/missing/a", json!(1)).is_err());
+    assert!(document.set("/a/1", json!(1)).is_err());
+    assert!(document.set("/b/c", json!(1)).is_err());
+    assert!(document.set("a", json!(1)).is_err());
+    assert!(document.remove("").is_err());
+    assert!(document.remove("/c").is_err());
+    assert!(document.remove("/a/1").is_err());
+    assert_canonical(&mut document)?;
+
+    document.set("/b", json!(u64::MAX))?;
+    assert!(document.to_vec().is_err());
+    document.set("/b", json!(1))?;
+    assert_eq!(document.to_vec()?, br#"{"a":[1],"b":1}"#);
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::io;
+
+use json_canon::{
+    escape::{is_canonical, to_string, to_vec, Escaping},
+    CanonicalFormatter,
+};
+use serde::Serialize;
+use serde_json::{from_slice, from_str, json, ser::PrettyFormatter, Serializer, Value};
+
+#[test]
+fn test_ascii() -> io::Result<()> {
+    let value = json!({
+        "plain": "abc ~",
+        "bmp": "caf\u{e9} \u{20ac}\u{fffd}",
+        "astral": "\u{1f600}\u{10ffff}",
+        "control": "\n\u{1f}\"\\",
+    });
+    assert_eq!(
+        to_string(&value, Escaping::Ascii)?,
+        r#"{"astral":"\ud83d\ude00\udbff\udfff","bmp":"caf\u00e9 \u20ac\ufffd","control":"\n\u001f\"\\","plain":"abc ~"}"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_ascii_sorts_unescaped_keys() -> io::Result<()> {
+    // Escaped, "\u00e9" would sort before "z", and "\ud83d\ude00" before "\ufb01".
+    let value = json!({ "\u{e9}": 1, "z": 2, "\u{fb01}": 3, "\u{1f600}": 4 });
+    assert_eq!(
+        to_string(&value, Escaping::Ascii)?,
+        r#"{"z":2,"\u00e9":1,"\ud83d\ude00":4,"\ufb01":3}"#
+    );
+    assert_eq!(
+        to_string(&value, Escaping::Jcs)?,
+        json_canon::to_string(&value)?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_ascii_fixtures() -> io::Result<()> {
+    for input in [
+        include_str!("../../../test-data/input/french.json"),
+        include_str!("../../../test-data/input/unicode.json"),
+        include_str!("../../../test-data/input/weird.json"),
+    ] {
+        let value: Value = from_str(input)?;
+        let ascii = to_vec(&value, Escaping::Ascii)?;
+        assert!(ascii.is_ascii());
+        assert_eq!(from_slice::<Value>(&ascii)?, value);
+        assert!(is_canonical(&ascii, Escaping::Ascii)?);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_is_canonical() -> io::Result<()> {
+    let jcs = "{\"a\":\"\u{e9}\"}";
+    assert!(is_canonical(jcs.as_bytes(), Escaping::Jcs)?);
+    assert!(!is_canonical(jcs.as_bytes(), Escaping::Ascii)?);
+    assert!(is_canonical(br#"{"a":"\u00e9"}"#, Escaping::Ascii)?);
+    assert!(!is_canonical(br#"{"a":"\u00E9"}"#, Escaping::Ascii)?);
+    assert!(is_canonical(b"{", Escaping::Ascii).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_ascii_pretty() -> io::Result<()> {
+    let mut data = Vec::new();
+    let formatter =
+        CanonicalFormatter::with_formatter(PrettyFormatter::new()).escaping(Escaping::Ascii);
+    json!({ "\u{e9}": ["\u{e9}"] })
+        .serialize(&mut Serializer::with_formatter(&mut data, formatter))?;
+    assert_eq!(
+        String::from_utf8(data).unwrap(),
+        "{\n  \"\\u00e9\": [\n    \"\\u00e9\"\n  ]\n}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_html() -> io::Result<()> {
+    let value = json!({
+        "<b>": "</script><script>alert(1 && 2)</script>",
+        "lines": "a\u{2028}b\u{2029}c\u{e9}\u{1f600}",
+        "n": 1.5e21,
+    });
+    // The same as Go's `json.Marshal` of the value.
+    assert_eq!(
+        to_string(&value, Escaping::Html)?,
+        r#"{"\u003cb\u003e":"\u003c/script\u003e\u003cscript\u003ealert(1 \u0026\u0026 2)\u003c/script\u003e","lines":"a\u2028b\u2029cé😀","n":1.5e+21}"#
+    );
+    assert!(is_canonical(
+        to_string(&value, Escaping::Html)?.as_bytes(),
+        Escaping::Html
+    )?);
+    assert!(!is_canonical(
+        json_canon::to_string(&value)?.as_bytes(),
+        Escaping::Html
+    )?);
+    Ok(())
+}
+
+#[test]
+fn test_html_controls() -> io::Result<()> {
+    let value = json!("\u{8}\u{c}\n\u{1}");
+    assert_eq!(to_string(&value, Escaping::Html)?, r#""\b\f\n\u0001""#);
+    Ok(())
+}
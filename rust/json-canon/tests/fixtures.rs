@@ -1,15 +1,39 @@
 use std::io::Error;
 
-use json_canon::to_string;
+use json_canon::{to_string, to_string_pretty};
 use serde_json::{from_str, Value};
 
 #[track_caller]
 fn test_data_import(input: &str, expected: &str) -> Result<(), Error> {
-    let actual = to_string(&from_str::<Value>(input.trim())?)?;
+    let value = from_str::<Value>(input.trim())?;
+    let actual = to_string(&value)?;
     assert_eq!(actual, expected.trim());
+    let pretty = to_string_pretty(&value)?;
+    assert_eq!(strip_whitespace(&pretty), expected.trim());
     Ok(())
 }
 
+/// Remove the whitespace between tokens.
+fn strip_whitespace(json: &str) -> String {
+    let mut stripped = String::with_capacity(json.len());
+    let (mut in_string, mut escaped) = (false, false);
+    for c in json.chars() {
+        if in_string {
+            match (escaped, c) {
+                (false, '\\') => escaped = true,
+                (false, '"') => in_string = false,
+                _ => escaped = false,
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c.is_ascii_whitespace() {
+            continue;
+        }
+        stripped.push(c);
+    }
+    stripped
+}
+
 #[test]
 fn arrays() -> Result<(), Error> {
     test_data_import(
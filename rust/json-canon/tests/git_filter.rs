@@ -0,0 +1,213 @@
+#![cfg(feature = "git-filter")]
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use json_canon::git_filter::filter_process;
+
+/// One end of an in-memory pipe, where written data is only sent when flushed, so a missing
+/// flush deadlocks (and times out) like a real pipe to Git would.
+struct PipeWriter {
+    buffer: Vec<u8>,
+    sender: Sender<Vec<u8>>,
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            let data = std::mem::take(&mut self.buffer);
+            self.sender
+                .send(data)
+                .map_err(|_| io::Error::from(ErrorKind::BrokenPipe))?;
+        }
+        Ok(())
+    }
+}
+
+struct PipeReader {
+    receiver: Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.buffer.len() {
+            match self.receiver.recv_timeout(Duration::from_secs(5)) {
+                Ok(buffer) => {
+                    self.buffer = buffer;
+                    self.position = 0;
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(0),
+                Err(RecvTimeoutError::Timeout) => return Err(ErrorKind::TimedOut.into()),
+            }
+        }
+        let len = data.len().min(self.buffer.len() - self.position);
+        data[..len].copy_from_slice(&self.buffer[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+fn pipe() -> (PipeWriter, PipeReader) {
+    let (sender, receiver) = channel();
+    (
+        PipeWriter {
+            buffer: Vec::new(),
+            sender,
+        },
+        PipeReader {
+            receiver,
+            buffer: Vec::new(),
+            position: 0,
+        },
+    )
+}
+
+/// Git's side of the filter process.
+struct Git {
+    writer: PipeWriter,
+    reader: PipeReader,
+    filter: JoinHandle<io::Result<()>>,
+}
+
+impl Git {
+    fn start() -> Self {
+        let (writer, filter_reader) = pipe();
+        let (filter_writer, reader) = pipe();
+        let filter = thread::spawn(move || filter_process(filter_reader, filter_writer));
+        Git {
+            writer,
+            reader,
+            filter,
+        }
+    }
+
+    fn send_packet(&mut self, data: &[u8]) {
+        write!(self.writer, "{:04x}", data.len() + 4).unwrap();
+        self.writer.write_all(data).unwrap();
+    }
+
+    fn send_list(&mut self, lines: &[&str]) {
+        for line in lines {
+            self.send_packet(format!("{}\n", line).as_bytes());
+        }
+        self.send_flush();
+    }
+
+    fn send_flush(&mut self) {
+        self.writer.write_all(b"0000").unwrap();
+        self.writer.flush().unwrap();
+    }
+
+    /// Receive packets up to a flush packet.
+    fn receive_list(&mut self) -> io::Result<Vec<Vec<u8>>> {
+        let mut list = Vec::new();
+        loop {
+            let mut length = [0; 4];
+            self.reader.read_exact(&mut length)?;
+            let length = usize::from_str_radix(std::str::from_utf8(&length).unwrap(), 16).unwrap();
+            if length == 0 {
+                return Ok(list);
+            }
+            let mut data = vec![0; length - 4];
+            self.reader.read_exact(&mut data)?;
+            list.push(data);
+        }
+    }
+
+    fn handshake(&mut self) -> io::Result<()> {
+        self.send_list(&["git-filter-client", "version=2"]);
+        assert_eq!(
+            self.receive_list()?,
+            vec![b"git-filter-server\n".to_vec(), b"version=2\n".to_vec()]
+        );
+        self.send_list(&["capability=clean", "capability=smudge", "capability=delay"]);
+        assert_eq!(
+            self.receive_list()?,
+            vec![
+                b"capability=clean\n".to_vec(),
+                b"capability=smudge\n".to_vec()
+            ]
+        );
+        Ok(())
+    }
+
+    /// Send a command, and receive the filtered content, or `None` if the status is an error.
+    fn command(&mut self, command: &str, content: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        self.send_list(&[&format!("command={}", command), "pathname=file.json"]);
+        for chunk in content.chunks(65516) {
+            self.send_packet(chunk);
+        }
+        self.send_flush();
+
+        let status = self.receive_list()?;
+        if status == vec![b"status=error\n".to_vec()] {
+            return Ok(None);
+        }
+        assert_eq!(status, vec![b"status=success\n".to_vec()]);
+        let content = self.receive_list()?.concat();
+        assert!(self.receive_list()?.is_empty());
+        Ok(Some(content))
+    }
+
+    fn stop(self) -> io::Result<()> {
+        drop(self.writer);
+        self.filter.join().unwrap()
+    }
+}
+
+#[test]
+fn test_clean_and_smudge() -> io::Result<()> {
+    let mut git = Git::start();
+    git.handshake()?;
+
+    let cleaned = git.command("clean", b"{\n  \"b\": [1.0, 2],\n  \"a\": \"\\u0041\"\n}\n")?;
+    assert_eq!(cleaned.unwrap(), br#"{"a":"A","b":[1,2]}"#);
+
+    let smudged = git.command(
+        "smudge",
+        br#"{"\ufb01":{},"a":"A","b":[1,2],"\ud83d\ude00":null}"#,
+    )?;
+    assert_eq!(
+        String::from_utf8(smudged.unwrap()).unwrap(),
+        "{\n  \"a\": \"A\",\n  \"b\": [\n    1,\n    2\n  ],\n  \"\u{1f600}\": null,\n  \"\u{fb01}\": {}\n}\n"
+    );
+    git.stop()
+}
+
+#[test]
+fn test_large_file() -> io::Result<()> {
+    let large: Vec<u32> = (0..50_000).collect();
+    let mut git = Git::start();
+    git.handshake()?;
+    let cleaned = git.command("clean", &serde_json::to_vec_pretty(&large)?)?;
+    assert_eq!(cleaned.unwrap(), serde_json::to_vec(&large)?);
+    git.stop()
+}
+
+#[test]
+fn test_error_status() -> io::Result<()> {
+    let mut git = Git::start();
+    git.handshake()?;
+    assert_eq!(git.command("clean", b"{")?, None);
+    assert_eq!(git.command("unknown", b"{}")?, None);
+    assert_eq!(git.command("clean", b"{}")?, Some(b"{}".to_vec()));
+    git.stop()
+}
+
+#[test]
+fn test_unsupported_version() {
+    let mut git = Git::start();
+    git.send_list(&["git-filter-client", "version=3"]);
+    assert!(git.stop().is_err());
+}
@@ -0,0 +1,95 @@
+use std::{
+    any::type_name,
+    collections::{BTreeSet, HashMap, HashSet},
+    io,
+};
+
+use json_canon::{
+    lint::{set_warnings, unordered, Unordered},
+    sorted::CanonicalSet,
+    to_string,
+};
+use serde_derive::Serialize;
+use serde_json::json;
+
+#[derive(Serialize)]
+enum Event {
+    Tagged { tags: HashSet<u8> },
+}
+
+#[derive(Serialize)]
+struct Document {
+    tags: HashSet<String>,
+    sorted: CanonicalSet<HashSet<String>>,
+    #[serde(with = "json_canon::sorted::seq")]
+    adapted: HashSet<String>,
+    ordered: BTreeSet<String>,
+    groups: HashMap<String, Vec<HashSet<u8>>>,
+    events: Vec<Event>,
+    maybe: Option<HashSet<u8>>,
+}
+
+#[test]
+fn test_unordered() {
+    let document = Document {
+        tags: HashSet::new(),
+        sorted: CanonicalSet(HashSet::new()),
+        adapted: HashSet::new(),
+        ordered: BTreeSet::new(),
+        groups: [("a/b".to_owned(), vec![HashSet::new()])]
+            .iter()
+            .cloned()
+            .collect(),
+        events: vec![Event::Tagged {
+            tags: HashSet::new(),
+        }],
+        maybe: Some(HashSet::new()),
+    };
+    let paths: Vec<String> = unordered(&document)
+        .into_iter()
+        .map(|Unordered { path, .. }| path)
+        .collect();
+    assert_eq!(
+        paths,
+        vec!["/tags", "/groups/a~1b/0", "/events/0/Tagged/tags", "/maybe"]
+    );
+}
+
+#[test]
+fn test_type_name() {
+    let found = unordered(&HashSet::<u8>::new());
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].path, "");
+    assert!(found[0].type_name.contains("HashSet<u8"));
+}
+
+#[test]
+fn test_values_are_ordered() {
+    assert!(unordered(&json!({ "a": [1, 2], "b": { "c": null } })).is_empty());
+}
+
+#[test]
+fn test_nested_in_canonical_set() {
+    let sets = CanonicalSet(vec![HashSet::<u8>::new(), HashSet::new()]);
+    let paths: Vec<String> = unordered(&sets)
+        .into_iter()
+        .map(|Unordered { path, .. }| path)
+        .collect();
+    assert_eq!(paths, vec!["/0", "/1"]);
+}
+
+#[test]
+fn test_std_type_name() {
+    // The lint matches this name, which `std` doesn't promise to keep.
+    assert!(type_name::<HashSet<u8>>().starts_with("std::collections::hash::set::HashSet<"));
+}
+
+#[test]
+fn test_serialize_unordered() -> io::Result<()> {
+    let tags: HashSet<u8> = [1].iter().copied().collect();
+    assert_eq!(to_string(&tags)?, "[1]");
+    set_warnings(false);
+    assert_eq!(to_string(&tags)?, "[1]");
+    set_warnings(true);
+    Ok(())
+}
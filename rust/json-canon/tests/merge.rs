@@ -0,0 +1,125 @@
+use std::io;
+
+use json_canon::{merge, merge::Conflict, to_vec};
+use serde_json::{from_str, json, Value};
+
+#[test]
+fn test_clean_merge() -> io::Result<()> {
+    let base = json!({
+        "name": "service",
+        "replicas": 1,
+        "env": { "LOG": "info", "PORT": "80" },
+        "tags": ["a"]
+    });
+    let ours = json!({
+        "name": "service",
+        "replicas": 3,
+        "env": { "LOG": "debug", "PORT": "80" },
+        "tags": ["a"]
+    });
+    let theirs = json!({
+        "name": "service",
+        "replicas": 1,
+        "env": { "LOG": "info", "PORT": "8080", "HOST": "0.0.0.0" },
+        "tags": ["a", "b"]
+    });
+
+    let merged = merge(&base, &ours, &theirs);
+    assert!(merged.is_clean());
+    assert_eq!(
+        merged.to_vec()?,
+        br#"{"env":{"HOST":"0.0.0.0","LOG":"debug","PORT":"8080"},"name":"service","replicas":3,"tags":["a","b"]}"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_removals() -> io::Result<()> {
+    let base = json!({ "a": 1, "b": 2, "c": 3 });
+    let ours = json!({ "b": 2, "c": 3 });
+    let theirs = json!({ "a": 1, "c": 3, "d": 4 });
+    let merged = merge(&base, &ours, &theirs);
+    assert_eq!(merged.to_vec()?, br#"{"c":3,"d":4}"#);
+    Ok(())
+}
+
+#[test]
+fn test_same_change_on_both_sides() -> io::Result<()> {
+    let base: Value = from_str(r#"{"a": 1, "b": {"c": 1}}"#)?;
+    let ours: Value = from_str(r#"{"a": 2.0, "b": {"c": 1, "d": 1}}"#)?;
+    let theirs: Value = from_str(r#"{"a": 2, "b": {"d": 1e0, "c": 1}}"#)?;
+    let merged = merge(&base, &ours, &theirs);
+    assert!(merged.is_clean());
+    assert_eq!(merged.to_vec()?, br#"{"a":2,"b":{"c":1,"d":1}}"#);
+    Ok(())
+}
+
+#[test]
+fn test_conflicts() -> io::Result<()> {
+    let base = json!({ "a": 1, "b": [1], "c": { "d": 1 }, "e": 1 });
+    let ours = json!({ "a": 2, "b": [1, 2], "c": { "d": 2 }, "f": { "g": 1, "h": 1 } });
+    let theirs = json!({ "a": 3, "b": [0, 1], "c": 5, "e": 2, "f": { "g": 2, "h": 1 } });
+
+    let merged = merge(&base, &ours, &theirs);
+    assert!(!merged.is_clean());
+    assert_eq!(
+        merged
+            .conflicts()
+            .iter()
+            .map(|conflict| conflict.pointer.as_str())
+            .collect::<Vec<_>>(),
+        vec!["/a", "/b", "/c", "/e", "/f/g"]
+    );
+    assert_eq!(
+        merged.conflicts()[3],
+        Conflict {
+            pointer: "/e".into(),
+            base: Some(json!(1)),
+            ours: None,
+            theirs: Some(json!(2)),
+        }
+    );
+
+    let err = merged.to_vec().unwrap_err();
+    assert_eq!(err.to_string(), "Merge has 5 conflict(s), first at \"/a\".");
+    assert_eq!(
+        merged.value(),
+        &json!({ "a": 2, "b": [1, 2], "c": { "d": 2 }, "f": { "g": 1, "h": 1 } })
+    );
+    assert!(merged.into_result().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_conflict_pointers_are_escaped() {
+    let base = json!({ "a/b": { "~": 1 } });
+    let ours = json!({ "a/b": { "~": 2 } });
+    let theirs = json!({ "a/b": { "~": 3 } });
+    let merged = merge(&base, &ours, &theirs);
+    assert_eq!(merged.conflicts()[0].pointer, "/a~1b/~0");
+}
+
+#[test]
+fn test_root_changes() -> io::Result<()> {
+    let merged = merge(&json!(1), &json!(1), &json!([2]));
+    assert_eq!(merged.to_vec()?, b"[2]");
+
+    let merged = merge(&json!(1), &json!(2), &json!([2]));
+    assert_eq!(merged.conflicts()[0].pointer, "");
+    Ok(())
+}
+
+#[test]
+fn test_merge_matches_to_vec() -> io::Result<()> {
+    let base: Value = from_str(include_str!("../../../test-data/input/structures.json"))?;
+    let mut ours = base.clone();
+    ours["1"]["f"]["new"] = json!("ours");
+    let mut theirs = base.clone();
+    theirs["111"] = json!([]);
+
+    let merged = merge(&base, &ours, &theirs);
+    let mut expected = ours.clone();
+    expected["111"] = json!([]);
+    assert_eq!(merged.to_vec()?, to_vec(&expected)?);
+    Ok(())
+}
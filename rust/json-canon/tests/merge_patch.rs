@@ -0,0 +1,109 @@
+use std::io;
+
+use json_canon::{merge_patch, merge_patch::apply_merge_patch, merge_patch_to_writer, to_vec};
+use serde_json::{from_str, json, Value};
+
+#[track_caller]
+fn test_merge_patch(target: &str, patch: &str, expected: &str) -> io::Result<()> {
+    let original: Value = from_str(target)?;
+    let patch: Value = from_str(patch)?;
+
+    let mut target = original.clone();
+    let actual = merge_patch(&mut target, &patch)?;
+    assert_eq!(String::from_utf8(actual).unwrap(), expected);
+    assert_eq!(to_vec(&target)?, expected.as_bytes());
+
+    let mut target = original;
+    let mut actual = Vec::new();
+    merge_patch_to_writer(&mut actual, &mut target, &patch)?;
+    assert_eq!(String::from_utf8(actual).unwrap(), expected);
+    assert_eq!(to_vec(&target)?, expected.as_bytes());
+    Ok(())
+}
+
+// Test cases from RFC 7396, appendix A.
+#[test]
+fn test_rfc_examples() -> io::Result<()> {
+    test_merge_patch(r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#)?;
+    test_merge_patch(r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#)?;
+    test_merge_patch(r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#)?;
+    test_merge_patch(r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#)?;
+    test_merge_patch(r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#)?;
+    test_merge_patch(r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#)?;
+    test_merge_patch(
+        r#"{"a":{"b":"c"}}"#,
+        r#"{"a":{"b":"d","c":null}}"#,
+        r#"{"a":{"b":"d"}}"#,
+    )?;
+    test_merge_patch(r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#)?;
+    test_merge_patch(r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#)?;
+    test_merge_patch(r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#)?;
+    test_merge_patch(r#"{"a":"foo"}"#, r#"null"#, r#"null"#)?;
+    test_merge_patch(r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#)?;
+    test_merge_patch(r#"{"e":null}"#, r#"{"a":1}"#, r#"{"a":1,"e":null}"#)?;
+    test_merge_patch(r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#)?;
+    test_merge_patch(
+        r#"{}"#,
+        r#"{"a":{"bb":{"ccc":null}}}"#,
+        r#"{"a":{"bb":{}}}"#,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_canonical_output() -> io::Result<()> {
+    test_merge_patch(
+        r#"{"title": "Goodbye!", "author": {"givenName": "John", "familyName": "Doe"}, "tags": ["example", "sample"], "content": "This will be unchanged"}"#,
+        r#"{"title": "Hello!", "phoneNumber": "+01-123-456-7890", "author": {"familyName": null}, "tags": ["example"], "price": 1.50e1}"#,
+        r#"{"author":{"givenName":"John"},"content":"This will be unchanged","phoneNumber":"+01-123-456-7890","price":15,"tags":["example"],"title":"Hello!"}"#,
+    )
+}
+
+#[test]
+fn test_apply_in_place() {
+    let mut target = json!({ "a": { "b": 1 } });
+    apply_merge_patch(&mut target, &json!({ "a": { "c": 2 } }));
+    assert_eq!(target, json!({ "a": { "b": 1, "c": 2 } }));
+}
+
+#[cfg(feature = "hash")]
+#[test]
+fn test_merge_patch_digest() -> io::Result<()> {
+    use json_canon::{hash::to_hash, merge_patch::MergePatchDigest, merge_patch_digest};
+
+    let original = json!({ "a": 1, "b": [true] });
+    let mut target = original.clone();
+    let digest = merge_patch_digest(&mut target, &json!({ "a": 2 }))?;
+    assert_eq!(
+        digest,
+        MergePatchDigest {
+            before: to_hash(&original)?,
+            after: to_hash(&json!({ "a": 2, "b": [true] }))?,
+        }
+    );
+    assert!(!digest.is_unchanged());
+
+    let digest = merge_patch_digest(&mut target, &from_str(r#"{"a": 2.0, "c": null}"#)?)?;
+    assert!(digest.is_unchanged());
+    Ok(())
+}
+
+#[test]
+fn test_invalid_number() -> io::Result<()> {
+    let original = json!({ "b": 1 });
+    let patch = json!({ "a": u64::MAX, "b": null });
+
+    let mut target = original.clone();
+    assert!(merge_patch(&mut target, &patch).is_err());
+    assert_eq!(target, original);
+
+    assert!(merge_patch_to_writer(Vec::new(), &mut target, &patch).is_err());
+    assert_eq!(target, original);
+
+    #[cfg(feature = "hash")]
+    {
+        assert!(json_canon::merge_patch_digest(&mut target, &patch).is_err());
+        assert_eq!(target, original);
+    }
+    Ok(())
+}
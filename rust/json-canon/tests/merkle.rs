@@ -0,0 +1,102 @@
+#![cfg(feature = "hash")]
+
+use std::io;
+
+use json_canon::merkle::{leaf_hash, MerkleTree};
+use serde_json::{json, Value};
+
+fn to_hex(hash: &[u8]) -> String {
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[test]
+fn test_empty_root() -> io::Result<()> {
+    let tree = MerkleTree::from_elements(Vec::<Value>::new())?;
+    assert!(tree.is_empty());
+    assert_eq!(
+        to_hex(&tree.root()),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(tree.proof(0), None);
+    Ok(())
+}
+
+#[test]
+fn test_leaf_hash() -> io::Result<()> {
+    // SHA-256(0x00 || "null")
+    assert_eq!(
+        to_hex(&leaf_hash(&json!(null))?),
+        "b018d21dce8b6a938aecc2bb5ffdef6c1cd0d821f0f282c0eb96a08a36ef7bfe"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_root() -> io::Result<()> {
+    let elements = json!([1, "a", { "b": 2, "a": 1 }]);
+    let tree = MerkleTree::from_elements(elements.as_array().unwrap())?;
+    assert_eq!(tree.len(), 3);
+    // SHA-256(0x01 || SHA-256(0x01 || leaf(1) || leaf("a")) || leaf({"a":1,"b":2}))
+    assert_eq!(
+        to_hex(&tree.root()),
+        "23a1bded47a8cbeff069044055e5ba3bf86a295d413cd7f09b8b856f94183ce2"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_from_reader() -> io::Result<()> {
+    let input = r#" [ 1.0, "a", { "b": 2, "a": 1e0 } ] "#;
+    let tree = MerkleTree::from_reader(input.as_bytes())?;
+    let expected =
+        MerkleTree::from_elements(vec![json!(1), json!("a"), json!({ "a": 1, "b": 2 })])?;
+    assert_eq!(tree, expected);
+
+    assert!(MerkleTree::from_reader(r#"{"a":1}"#.as_bytes()).is_err());
+    assert!(MerkleTree::from_reader(r#"[1] [2]"#.as_bytes()).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_inclusion_proofs() -> io::Result<()> {
+    for size in 1..=33_u64 {
+        let elements: Vec<Value> = (0..size).map(|i| json!({ "i": i })).collect();
+        let tree = MerkleTree::from_elements(&elements)?;
+        let root = tree.root();
+
+        for (index, element) in elements.iter().enumerate() {
+            let proof = tree.proof(index).unwrap();
+            assert_eq!(proof.tree_size, elements.len());
+            assert!(
+                proof.verify(element, &root)?,
+                "size {} index {}",
+                size,
+                index
+            );
+
+            let other = json!({ "i": size });
+            assert!(!proof.verify(&other, &root)?);
+
+            let mut wrong_index = proof.clone();
+            wrong_index.index = (index + 1) % elements.len();
+            if wrong_index.index != index {
+                assert!(!wrong_index.verify(element, &root)?);
+            }
+
+            let mut wrong_size = proof.clone();
+            wrong_size.tree_size += 1;
+            if size.is_power_of_two() {
+                assert!(!wrong_size.verify(element, &root)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_proof_is_canonical() -> io::Result<()> {
+    let tree = MerkleTree::from_reader(r#"[{"b":1,"a":2},[]]"#.as_bytes())?;
+    let proof = tree.proof(0).unwrap();
+    assert!(proof.verify(&json!({ "a": 2, "b": 1 }), &tree.root())?);
+    Ok(())
+}
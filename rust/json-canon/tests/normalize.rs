@@ -0,0 +1,98 @@
+#![cfg(feature = "normalize")]
+
+use std::io;
+
+use json_canon::{
+    escape::Escaping,
+    normalize::{to_string, Normalization},
+    to_string as to_jcs_string, CanonicalFormatter,
+};
+use serde::Serialize;
+use serde_json::{from_str, json, Serializer, Value};
+
+#[test]
+fn test_nfc() -> io::Result<()> {
+    let precomposed = json!({ "\u{e9}": "caf\u{e9}", "e": ["\u{212b}"] });
+    let decomposed = json!({ "e\u{301}": "cafe\u{301}", "e": ["A\u{30a}"] });
+    assert_ne!(to_jcs_string(&precomposed)?, to_jcs_string(&decomposed)?);
+
+    let expected = "{\"e\":[\"\u{c5}\"],\"\u{e9}\":\"caf\u{e9}\"}";
+    assert_eq!(to_string(&precomposed, Normalization::Nfc)?, expected);
+    assert_eq!(to_string(&decomposed, Normalization::Nfc)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_keys_sorted_after_normalization() -> io::Result<()> {
+    // "e\u{301}" sorts before "f" as is, but "\u{e9}" sorts after it.
+    let value = json!({ "e\u{301}": 1, "f": 2 });
+    assert_eq!(to_jcs_string(&value)?, "{\"e\u{301}\":1,\"f\":2}");
+    assert_eq!(
+        to_string(&value, Normalization::Nfc)?,
+        "{\"f\":2,\"\u{e9}\":1}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_nfkc() -> io::Result<()> {
+    let value = json!({ "\u{fb01}le": "\u{2460} x\u{b2}", "file": 1 });
+    assert_eq!(
+        to_string(&value["\u{fb01}le"], Normalization::Nfkc)?,
+        "\"1 x2\""
+    );
+    assert_eq!(
+        to_string(&value["\u{fb01}le"], Normalization::Nfc)?,
+        "\"\u{2460} x\u{b2}\""
+    );
+    // The keys are the same once normalized.
+    assert!(to_string(&value, Normalization::Nfkc).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_nfkc_escapes() -> io::Result<()> {
+    // U+FF02, U+FF3C, and U+FE68 are `"`, `\`, and `\` once normalized.
+    let value = json!({ "\u{ff02}a": "x\u{ff3c}y\u{fe68}z\u{ff02}", "b": 1 });
+    let nfkc = to_string(&value, Normalization::Nfkc)?;
+    assert_eq!(nfkc, r#"{"\"a":"x\\y\\z\"","b":1}"#);
+    assert_eq!(
+        from_str::<Value>(&nfkc)?,
+        json!({ "\"a": "x\\y\\z\"", "b": 1 })
+    );
+
+    // with the active escaping
+    let formatter = CanonicalFormatter::new()
+        .normalization(Normalization::Nfkc)
+        .escaping(Escaping::Ascii);
+    let mut ascii = Vec::new();
+    json!(["\u{ff02}\u{e9}\u{fe68}"])
+        .serialize(&mut Serializer::with_formatter(&mut ascii, formatter))?;
+    assert_eq!(ascii, br#"["\"\u00e9\\"]"#);
+    Ok(())
+}
+
+#[test]
+fn test_strict_nfc() -> io::Result<()> {
+    let value = json!({ "\u{e9}": ["caf\u{e9}", "\n\"", 1] });
+    assert_eq!(
+        to_string(&value, Normalization::StrictNfc)?,
+        to_jcs_string(&value)?
+    );
+
+    let error = to_string(&json!(["cafe\u{301}"]), Normalization::StrictNfc).unwrap_err();
+    assert!(error.to_string().contains("Normalization Form C"));
+    assert!(to_string(&json!({ "e\u{301}": 1 }), Normalization::StrictNfc).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_escapes_between_fragments() -> io::Result<()> {
+    // An escaped character is never composed with a combining character after it.
+    let value = json!("e\n\u{301}\"\u{301}");
+    assert_eq!(
+        to_string(&value, Normalization::Nfc)?,
+        "\"e\\n\u{301}\\\"\u{301}\""
+    );
+    Ok(())
+}
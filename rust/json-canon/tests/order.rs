@@ -0,0 +1,90 @@
+use std::{cmp::Ordering, io};
+
+use json_canon::{
+    order::{CodePointOrder, KeyOrder, Utf16Order},
+    to_string, CanonicalFormatter,
+};
+use serde::Serialize;
+use serde_json::{from_str, json, ser::CompactFormatter, Serializer, Value};
+
+fn to_string_with<K>(value: &Value, order: K) -> io::Result<String>
+where
+    K: KeyOrder,
+{
+    let mut data = Vec::new();
+    let formatter = CanonicalFormatter::new().key_order(order);
+    value.serialize(&mut Serializer::with_formatter(&mut data, formatter))?;
+    Ok(String::from_utf8(data).unwrap())
+}
+
+#[test]
+fn test_orders_differ_for_supplementary_keys() -> io::Result<()> {
+    // U+1F600 is U+D83D U+DE00 in UTF-16, which sorts before U+E000 and U+FFFD, but its code point
+    // sorts after them.
+    let value = json!({
+        "\u{fffd}": 1,
+        "\u{1f600}": 2,
+        "\u{e000}": 3,
+        "\u{ff61}": 4,
+        "a": 5,
+    });
+    assert_eq!(
+        to_string_with(&value, Utf16Order)?,
+        "{\"a\":5,\"\u{1f600}\":2,\"\u{e000}\":3,\"\u{ff61}\":4,\"\u{fffd}\":1}"
+    );
+    assert_eq!(to_string_with(&value, Utf16Order)?, to_string(&value)?);
+    assert_eq!(
+        to_string_with(&value, CodePointOrder)?,
+        "{\"a\":5,\"\u{e000}\":3,\"\u{ff61}\":4,\"\u{fffd}\":1,\"\u{1f600}\":2}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_orders_agree_within_plane() -> io::Result<()> {
+    // As in `test_bug_utf8_sort`, with every key in the Basic Multilingual Plane.
+    let value: Value = from_str(r#"{"�\u0017\\�4�":null,"�\u0017B��":null,"é":[{"z":1,"Ā":2}]}"#)?;
+    assert_eq!(
+        to_string_with(&value, CodePointOrder)?,
+        to_string_with(&value, Utf16Order)?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_nested_objects() -> io::Result<()> {
+    let value = json!({ "b": { "\u{10000}": [], "\u{ffff}": {} }, "a": null });
+    assert_eq!(
+        to_string_with(&value, CodePointOrder)?,
+        "{\"a\":null,\"b\":{\"\u{ffff}\":{},\"\u{10000}\":[]}}"
+    );
+    assert_eq!(
+        to_string_with(&value, Utf16Order)?,
+        "{\"a\":null,\"b\":{\"\u{10000}\":[],\"\u{ffff}\":{}}}"
+    );
+    Ok(())
+}
+
+/// A custom order: shorter keys first, then by code point.
+struct ShortestFirst;
+
+impl KeyOrder for ShortestFirst {
+    fn cmp(&self, a: &str, b: &str) -> Ordering {
+        a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+    }
+}
+
+#[test]
+fn test_custom_order() -> io::Result<()> {
+    let value = json!({ "ccc": 1, "a": 2, "bb": 3, "b": 4 });
+    assert_eq!(
+        to_string_with(&value, ShortestFirst)?,
+        r#"{"a":2,"b":4,"bb":3,"ccc":1}"#
+    );
+
+    let mut data = Vec::new();
+    let formatter = CanonicalFormatter::with_formatter(CompactFormatter).key_order(&ShortestFirst);
+    value.serialize(&mut Serializer::with_formatter(&mut data, formatter))?;
+    assert_eq!(data, br#"{"a":2,"b":4,"bb":3,"ccc":1}"#);
+    Ok(())
+}
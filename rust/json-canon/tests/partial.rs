@@ -0,0 +1,74 @@
+use std::io::{self, ErrorKind};
+
+use json_canon::{canonical_subtree, canonicalize_at, to_vec};
+use serde_json::{from_str, Value};
+
+const ENVELOPE: &str = r#"{
+  "header" : { "b": 2, "a": 1 },
+  "payload": { "z": [1.0, "A", {"y": 1e2, "x": null}], "a": true } ,
+  "signature": "abc"
+}
+"#;
+
+#[test]
+fn test_canonicalize_at() -> io::Result<()> {
+    let output = canonicalize_at(ENVELOPE.as_bytes(), "/payload")?;
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        r#"{
+  "header" : { "b": 2, "a": 1 },
+  "payload": {"a":true,"z":[1,"A",{"x":null,"y":100}]} ,
+  "signature": "abc"
+}
+"#
+    );
+
+    let output = canonicalize_at(ENVELOPE.as_bytes(), "/payload/z/2")?;
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        ENVELOPE.replace(r#"{"y": 1e2, "x": null}"#, r#"{"x":null,"y":100}"#)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_canonical_subtree() -> io::Result<()> {
+    assert_eq!(
+        canonical_subtree(ENVELOPE.as_bytes(), "/payload")?,
+        br#"{"a":true,"z":[1,"A",{"x":null,"y":100}]}"#
+    );
+    assert_eq!(
+        canonical_subtree(ENVELOPE.as_bytes(), "/payload/z/1")?,
+        br#""A""#
+    );
+    assert_eq!(
+        canonical_subtree(ENVELOPE.as_bytes(), "/signature")?,
+        br#""abc""#
+    );
+
+    let value: Value = from_str(ENVELOPE)?;
+    assert_eq!(canonical_subtree(ENVELOPE.as_bytes(), "")?, to_vec(&value)?);
+    Ok(())
+}
+
+#[test]
+fn test_escaped_keys() -> io::Result<()> {
+    let input = br#"{"a\/b": {"c~d": [0]}, "a": 1}"#;
+    assert_eq!(canonical_subtree(input, "/a~1b/c~0d/0")?, b"0");
+    assert_eq!(canonical_subtree(input, "/a")?, b"1");
+    Ok(())
+}
+
+#[test]
+fn test_errors() {
+    let input = br#"{"a": [1, 2], "b": {}, "c": 1, "c": 2}"#;
+    for pointer in ["/a/2", "/a/01", "/a/x", "/b/c", "/d", "/a/0/x", "a"] {
+        assert!(canonical_subtree(input, pointer).is_err(), "{}", pointer);
+    }
+    assert_eq!(
+        canonical_subtree(input, "/c").unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    assert!(canonicalize_at(br#"{"a": 1"#, "/a").is_err());
+    assert!(canonicalize_at(br#"{"a": 1} x"#, "/a").is_err());
+}
@@ -0,0 +1,184 @@
+use std::io;
+
+use json_canon::{diff, patch::Operation, to_string, to_vec, Patch};
+use serde_json::{from_str, json, Value};
+
+#[track_caller]
+fn test_diff(a: &Value, b: &Value) -> io::Result<Patch> {
+    let patch = diff(a, b);
+    let mut patched = a.clone();
+    patch.apply(&mut patched)?;
+    assert_eq!(to_vec(&patched)?, to_vec(b)?);
+    Ok(patch)
+}
+
+fn inputs() -> Vec<Value> {
+    [
+        include_str!("../../../test-data/input/arrays.json"),
+        include_str!("../../../test-data/input/french.json"),
+        include_str!("../../../test-data/input/structures.json"),
+        include_str!("../../../test-data/input/unicode.json"),
+        include_str!("../../../test-data/input/values.json"),
+        include_str!("../../../test-data/input/weird.json"),
+        r#"[1, 2, 3, 4, 5]"#,
+        r#"[5, 1, 3, {"a": 1}, 4]"#,
+        r#"{"a": [1, {"b": 2}], "c": null}"#,
+        r#"{"a": [{"b": 3}], "d": "e"}"#,
+        r#"null"#,
+    ]
+    .iter()
+    .map(|input| from_str(input))
+    .collect::<serde_json::Result<_>>()
+    .unwrap()
+}
+
+#[test]
+fn test_diff_and_apply() -> io::Result<()> {
+    let inputs = inputs();
+    for a in &inputs {
+        for b in &inputs {
+            let patch = test_diff(a, b)?;
+            if to_vec(a)? == to_vec(b)? {
+                assert!(patch.is_empty());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_diff_objects_in_canonical_order() -> io::Result<()> {
+    let a = json!({ "\u{fb01}": 1, "b": 2, "a": 3, "same": 4 });
+    let b = json!({ "\u{1f600}": 1, "c": 2, "a": 3.0, "same": 5 });
+    let patch = test_diff(&a, &b)?;
+    assert_eq!(
+        to_string(&patch)?,
+        r#"[{"op":"remove","path":"/b"},{"op":"add","path":"/c","value":2},{"op":"replace","path":"/same","value":5},{"op":"add","path":"/😀","value":1},{"op":"remove","path":"/ﬁ"}]"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_diff_arrays() -> io::Result<()> {
+    let a = json!([1, 2, { "x": 1, "y": 2 }, 4, 5]);
+    let b = json!([0, 1, { "x": 1, "y": 3 }, 4, 6]);
+    let patch = test_diff(&a, &b)?;
+    assert_eq!(
+        patch.operations(),
+        &[
+            Operation::Add {
+                path: "/0".into(),
+                value: json!(0)
+            },
+            Operation::Replace {
+                path: "/2".into(),
+                value: json!({ "x": 1, "y": 3 })
+            },
+            Operation::Remove { path: "/3".into() },
+            Operation::Replace {
+                path: "/4".into(),
+                value: json!(6)
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_diff_large_arrays() -> io::Result<()> {
+    // only the middle is compared
+    let a: Value = (0..100_000).collect();
+    let mut b = a.clone();
+    b[50_000] = json!(-1);
+    let patch = test_diff(&a, &b)?;
+    assert_eq!(
+        patch.operations(),
+        &[Operation::Replace {
+            path: "/50000".into(),
+            value: json!(-1)
+        }]
+    );
+
+    // too large to compare, so replaced
+    let a: Value = (0..2_000).collect();
+    let b: Value = (0..2_000).rev().collect();
+    let patch = test_diff(&a, &b)?;
+    assert_eq!(
+        patch.operations(),
+        &[Operation::Replace {
+            path: "".into(),
+            value: b
+        }]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_diff_escapes_paths() -> io::Result<()> {
+    let a = json!({ "a/b": { "c~d": 1 } });
+    let b = json!({ "a/b": { "c~d": 2 } });
+    let patch = test_diff(&a, &b)?;
+    assert_eq!(
+        to_string(&patch)?,
+        r#"[{"op":"replace","path":"/a~1b/c~0d","value":2}]"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_diff_numbers_canonically() -> io::Result<()> {
+    let a: Value = from_str(r#"{"a": 1.0, "b": [1e2]}"#)?;
+    let b: Value = from_str(r#"{"a": 1, "b": [100]}"#)?;
+    assert!(diff(&a, &b).is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_apply() -> io::Result<()> {
+    let patch: Patch = from_str(
+        r#"[
+            {"op": "test", "path": "/a", "value": 1.0},
+            {"op": "copy", "from": "/a", "path": "/b"},
+            {"op": "move", "from": "/c", "path": "/d/-"},
+            {"op": "add", "path": "/d/0", "value": "first"},
+            {"op": "replace", "path": "/a", "value": [true]}
+        ]"#,
+    )?;
+    let mut value = json!({ "a": 1, "c": "moved", "d": [] });
+    patch.apply(&mut value)?;
+    assert_eq!(
+        value,
+        json!({ "a": [true], "b": 1, "d": ["first", "moved"] })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_invalid_operations() {
+    assert!(from_str::<Patch>(r#"[{"op": "rename", "path": "/a"}]"#).is_err());
+    assert!(from_str::<Patch>(r#"[{"op": "add", "path": "/a"}]"#).is_err());
+    assert!(from_str::<Patch>(r#"[{"op": "move", "path": "/a"}]"#).is_err());
+    assert!(from_str::<Patch>(r#"[{"path": "/a"}]"#).is_err());
+    assert!(from_str::<Patch>(r#"[{"op": "remove", "path": 1}]"#).is_err());
+}
+
+#[test]
+fn test_apply_is_atomic() -> io::Result<()> {
+    let patch: Patch = from_str(
+        r#"[
+            {"op": "add", "path": "/b", "value": 2},
+            {"op": "test", "path": "/a", "value": 2}
+        ]"#,
+    )?;
+    let mut value = json!({ "a": 1 });
+    assert!(patch.apply(&mut value).is_err());
+    assert_eq!(value, json!({ "a": 1 }));
+
+    let patch: Patch = from_str(r#"[{"op": "move", "from": "/a", "path": "/a/b"}]"#)?;
+    assert!(patch.apply(&mut value).is_err());
+    let patch: Patch = from_str(r#"[{"op": "remove", "path": "/b"}]"#)?;
+    assert!(patch.apply(&mut value).is_err());
+    let patch: Patch = from_str(r#"[{"op": "add", "path": "/a/b/c", "value": 1}]"#)?;
+    assert!(patch.apply(&mut value).is_err());
+    Ok(())
+}
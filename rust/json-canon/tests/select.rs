@@ -0,0 +1,88 @@
+use std::io::{self, ErrorKind};
+
+use json_canon::select::select;
+use serde_json::json;
+
+#[test]
+fn test_select_json_path() -> io::Result<()> {
+    let value = json!({
+        "items": [
+            { "id": "a", "price": 1.50 },
+            { "id": "b", "price": 2 },
+            { "name": "no id" }
+        ],
+        "total": 3.5e0,
+        "note": "not covered"
+    });
+    let selection = select(&value, &["$.items[*].id", "$.total"])?;
+    assert_eq!(selection.len(), 3);
+    assert_eq!(
+        selection.to_vec()?,
+        br#"{"/items/0/id":"a","/items/1/id":"b","/total":3.5}"#
+    );
+    Ok(())
+}
+
+#[cfg(feature = "hash")]
+#[test]
+fn test_selection_hash() -> io::Result<()> {
+    use json_canon::hash::to_hash;
+
+    let value = json!({ "items": [{ "id": "a" }, { "id": "b" }], "total": 3.5e0 });
+    let selection = select(&value, &["$.items[*].id", "$.total"])?;
+    assert_eq!(
+        selection.to_hash()?,
+        to_hash(&json!({ "/items/0/id": "a", "/items/1/id": "b", "/total": 3.5 }))?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_select_json_path_syntax() -> io::Result<()> {
+    let value = json!({ "a/b": { "c'd": [10, 20] }, "e": { "f": 1, "g": 2 } });
+    let selection = select(&value, &[r#"$['a/b']["c'd"][1]"#, "$.e.*", "$"])?;
+    assert_eq!(selection.get("/a~1b/c'd/1"), Some(&json!(20)));
+    assert_eq!(selection.get("/e/f"), Some(&json!(1)));
+    assert_eq!(selection.get("/e/g"), Some(&json!(2)));
+    assert_eq!(selection.get(""), Some(&value));
+
+    assert!(select(&value, &["$.missing[*]", "$.e[0]"])?.is_empty());
+    for path in ["$.", "$[", "$[01]", "$['a", "$x", "$.e[-1]"] {
+        assert_eq!(
+            select(&value, &[path]).unwrap_err().kind(),
+            ErrorKind::InvalidInput,
+            "{}",
+            path
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_select_json_pointer() -> io::Result<()> {
+    let value = json!({ "a": [{ "b": 1 }], "c": null });
+    let selection = select(&value, &["/a/0/b", "/c"])?;
+    assert_eq!(selection.to_vec()?, br#"{"/a/0/b":1,"/c":null}"#);
+    assert_eq!(
+        select(&value, &["/d"]).unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+    assert_eq!(
+        select(&value, &["a"]).unwrap_err().kind(),
+        ErrorKind::InvalidInput
+    );
+    Ok(())
+}
+
+#[test]
+fn test_selection_is_order_independent() -> io::Result<()> {
+    let value = json!({ "x": { "\u{fb01}": 1, "\u{1f600}": 2 }, "y": 3 });
+    let a = select(&value, &["/y", "$.x.*"])?;
+    let b = select(&value, &["$.x['\u{1f600}']", "/x/\u{fb01}", "$.y"])?;
+    assert_eq!(a.to_vec()?, b.to_vec()?);
+    assert_eq!(
+        String::from_utf8(a.to_vec()?).unwrap(),
+        "{\"/x/\u{1f600}\":2,\"/x/\u{fb01}\":1,\"/y\":3}"
+    );
+    Ok(())
+}
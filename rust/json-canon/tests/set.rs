@@ -0,0 +1,149 @@
+use std::io;
+
+use json_canon::{
+    set::{to_string, SetPaths},
+    to_string as to_canonical_string, CanonicalFormatter,
+};
+use serde::Serialize;
+use serde_json::{json, Serializer};
+
+#[test]
+fn test_sorts_arrays_at_paths() -> io::Result<()> {
+    let value = json!({
+        "tags": ["b", "c", "a"],
+        "steps": ["b", "a"],
+    });
+    let sets = SetPaths::new(&["/tags"])?;
+    assert_eq!(
+        to_string(&value, &sets)?,
+        r#"{"steps":["b","a"],"tags":["a","b","c"]}"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_sorts_by_canonical_bytes() -> io::Result<()> {
+    // Numbers are sorted by their canonical bytes, not their value, and objects by their sorted
+    // keys.
+    let value = json!([10, 9, { "b": 1, "a": 2 }, { "a": 1 }, "x", [1, 2], [1], null, 1.0]);
+    let sets = SetPaths::new(&[""])?;
+    assert_eq!(
+        to_string(&value, &sets)?,
+        r#"["x",1,10,9,[1,2],[1],null,{"a":1},{"a":2,"b":1}]"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_dedupe() -> io::Result<()> {
+    let value = json!({ "tags": ["b", "a", "b", 1, 1.0, { "y": 1, "x": 2 }, { "x": 2, "y": 1 }] });
+    let sets = SetPaths::new(&["/tags"])?;
+    assert_eq!(
+        to_string(&value, &sets)?,
+        r#"{"tags":["a","b","b",1,1,{"x":2,"y":1},{"x":2,"y":1}]}"#
+    );
+    assert_eq!(
+        to_string(&value, &sets.dedupe(true))?,
+        r#"{"tags":["a","b",1,{"x":2,"y":1}]}"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_dedupe_at() -> io::Result<()> {
+    let value = json!({ "tags": ["b", "a", "b"], "scores": [2, 1, 2] });
+    let sets = SetPaths::new(&["/scores"])?.dedupe_at("/tags")?;
+    assert_eq!(
+        to_string(&value, &sets)?,
+        r#"{"scores":[1,2,2],"tags":["a","b"]}"#
+    );
+    assert_eq!(
+        to_string(&value, &sets.dedupe(true))?,
+        r#"{"scores":[1,2],"tags":["a","b"]}"#
+    );
+
+    // deduped if any matching pattern dedupes
+    let value = json!({ "users": [{ "roles": ["b", "a", "b"] }] });
+    let sets = SetPaths::new(&["/users/*/roles"])?.dedupe_at("/users/0/roles")?;
+    assert_eq!(
+        to_string(&value, &sets)?,
+        r#"{"users":[{"roles":["a","b"]}]}"#
+    );
+    assert!(SetPaths::new(&["/tags"])?.dedupe_at("tags").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_wildcards() -> io::Result<()> {
+    let value = json!({
+        "users": [
+            { "name": "b", "roles": ["write", "read"], "keys": [2, 1] },
+            { "name": "a", "roles": ["admin", "read"] },
+        ],
+        "roles": ["z", "y"],
+    });
+    let sets = SetPaths::new(&["/users/*/roles", "/users/0/keys"])?;
+    assert_eq!(
+        to_string(&value, &sets)?,
+        r#"{"roles":["z","y"],"users":[{"keys":[1,2],"name":"b","roles":["read","write"]},{"name":"a","roles":["admin","read"]}]}"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_nested_sets() -> io::Result<()> {
+    let value = json!({ "groups": [["b", "a"], ["a"], ["d", "c"]] });
+    let sets = SetPaths::new(&["/groups", "/groups/*"])?;
+    assert_eq!(
+        to_string(&value, &sets)?,
+        r#"{"groups":[["a","b"],["a"],["c","d"]]}"#
+    );
+
+    // The inner arrays are elements of the outer set, but not sets themselves.
+    let sets = SetPaths::new(&["/groups"])?;
+    assert_eq!(
+        to_string(&value, &sets)?,
+        r#"{"groups":[["a"],["b","a"],["d","c"]]}"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_escaped_keys() -> io::Result<()> {
+    let value = json!({ "a/b": [2, 1], "c~d": [4, 3], "\n": [6, 5] });
+    let sets = SetPaths::new(&["/a~1b", "/c~0d", "/\n"])?;
+    assert_eq!(
+        to_string(&value, &sets)?,
+        "{\"\\n\":[5,6],\"a/b\":[1,2],\"c~d\":[3,4]}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_no_paths_is_canonical() -> io::Result<()> {
+    let value = json!({ "b": [3, { "z": [2, 1], "y": null }], "a": [] });
+    let sets = SetPaths::new::<&str>(&[])?;
+    assert_eq!(to_string(&value, &sets)?, to_canonical_string(&value)?);
+    Ok(())
+}
+
+#[test]
+fn test_pretty() -> io::Result<()> {
+    // With a pretty formatter, elements are still sorted by their canonical bytes: `[1,2]` sorts
+    // before `[1]`, even though its pretty bytes would sort after.
+    let value = json!({ "lists": [[1], [1, 2], []] });
+    let mut data = Vec::new();
+    let formatter = CanonicalFormatter::pretty().sets(SetPaths::new(&["/lists"])?);
+    value.serialize(&mut Serializer::with_formatter(&mut data, formatter))?;
+    assert_eq!(
+        String::from_utf8(data).unwrap(),
+        "{\n  \"lists\": [\n    [\n      1,\n      2\n    ],\n    [\n      1\n    ],\n    []\n  ]\n}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_invalid_pattern() {
+    assert!(SetPaths::new(&["tags"]).is_err());
+    assert!(SetPaths::new(&["/tags~2"]).is_err());
+}
@@ -0,0 +1,113 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    io,
+};
+
+use json_canon::{
+    sorted::{CanonicalMap, CanonicalSet},
+    to_string,
+};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{from_str, json};
+
+#[test]
+fn test_set_is_sorted_by_canonical_bytes() -> io::Result<()> {
+    let set: HashSet<i64> = (0..100).collect();
+    let mut expected: Vec<String> = (0..100).map(|n| n.to_string()).collect();
+    expected.sort();
+    assert_eq!(
+        to_string(&CanonicalSet(set))?,
+        format!("[{}]", expected.join(","))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_set_is_deterministic() -> io::Result<()> {
+    // Each `HashSet` has its own random hasher, so iterates in its own order.
+    let strings: Vec<String> = (0..64).map(|n| format!("s{}", n)).collect();
+    let a: HashSet<&str> = strings.iter().map(String::as_str).collect();
+    let b: HashSet<&str> = strings.iter().rev().map(String::as_str).collect();
+    let ordered: BTreeSet<&str> = strings.iter().map(String::as_str).collect();
+    let a = to_string(&CanonicalSet(a))?;
+    assert_eq!(a, to_string(&ordered)?);
+    assert_eq!(a, to_string(&CanonicalSet(b))?);
+    Ok(())
+}
+
+#[test]
+fn test_set_of_objects() -> io::Result<()> {
+    let set = CanonicalSet(vec![
+        json!({ "b": 1, "a": 2 }),
+        json!({ "a": 1 }),
+        json!("x"),
+    ]);
+    assert_eq!(to_string(&set)?, r#"["x",{"a":1},{"a":2,"b":1}]"#);
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Record {
+    #[serde(with = "json_canon::sorted::seq")]
+    tags: HashSet<String>,
+    #[serde(with = "json_canon::sorted::map")]
+    counts: HashMap<String, u32>,
+    ids: CanonicalSet<HashSet<u32>>,
+}
+
+#[test]
+fn test_adapters() -> io::Result<()> {
+    let record = Record {
+        tags: ["c", "a", "b"].iter().map(|tag| tag.to_string()).collect(),
+        counts: [("y", 1), ("x", 2)]
+            .iter()
+            .map(|(key, count)| (key.to_string(), *count))
+            .collect(),
+        ids: CanonicalSet([3, 20, 1].iter().copied().collect()),
+    };
+    let json = to_string(&record)?;
+    assert_eq!(
+        json,
+        r#"{"counts":{"x":2,"y":1},"ids":[1,20,3],"tags":["a","b","c"]}"#
+    );
+    assert_eq!(from_str::<Record>(&json)?, record);
+    Ok(())
+}
+
+#[test]
+fn test_map_is_sorted_without_canonical_formatter() -> io::Result<()> {
+    let map: HashMap<String, u32> = (0..32).map(|n| (format!("k{}", n), n)).collect();
+    let canonical = to_string(&map)?;
+    assert_eq!(serde_json::to_string(&CanonicalMap(map))?, canonical);
+    Ok(())
+}
+
+#[test]
+fn test_map_is_sorted_as_object_keys() -> io::Result<()> {
+    // Sorted by serialized bytes, `"a b"` would come before `"a"`, and `"\n"` after `"\u0001"`.
+    let keys = [
+        "a b",
+        "a",
+        "ab",
+        "\u{1}",
+        "\n",
+        "\"",
+        "\u{20ac}",
+        "\u{1f600}",
+        "\u{fb33}",
+    ];
+    let map: HashMap<&str, usize> = keys.iter().enumerate().map(|(n, key)| (*key, n)).collect();
+    let canonical = to_string(&map)?;
+    assert_eq!(
+        canonical,
+        r#"{"\u0001":3,"\n":4,"\"":5,"a":1,"a b":0,"ab":2,"€":6,"😀":7,"דּ":8}"#
+    );
+    assert_eq!(serde_json::to_string(&CanonicalMap(map))?, canonical);
+
+    let map: HashMap<u32, u32> = [(10, 0), (9, 1), (100, 2)].iter().copied().collect();
+    assert_eq!(
+        serde_json::to_string(&CanonicalMap(map))?,
+        r#"{"10":0,"100":2,"9":1}"#
+    );
+    Ok(())
+}
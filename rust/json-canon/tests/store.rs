@@ -0,0 +1,184 @@
+#![cfg(feature = "hash")]
+
+use std::{env::temp_dir, fs, io, process, thread};
+
+use json_canon::{
+    hash::{to_hash, to_hex},
+    store::{Backend, FsBackend, MemoryBackend, Store},
+    to_vec,
+};
+use serde_json::{from_str, json, Value};
+
+#[track_caller]
+fn test_round_trip<B>(store: &mut Store<B>, input: &str) -> io::Result<()>
+where
+    B: Backend,
+{
+    let value: Value = from_str(input.trim())?;
+    let hash = store.put(&value)?;
+    assert_eq!(hash, to_hash(&value)?);
+    assert_eq!(store.to_vec(&hash)?, to_vec(&value)?);
+    assert_eq!(to_vec(&store.get(&hash)?)?, to_vec(&value)?);
+    Ok(())
+}
+
+#[test]
+fn test_memory_round_trip() -> io::Result<()> {
+    let mut store = Store::new(MemoryBackend::new());
+    test_round_trip(
+        &mut store,
+        include_str!("../../../test-data/input/arrays.json"),
+    )?;
+    test_round_trip(
+        &mut store,
+        include_str!("../../../test-data/input/french.json"),
+    )?;
+    test_round_trip(
+        &mut store,
+        include_str!("../../../test-data/input/structures.json"),
+    )?;
+    test_round_trip(
+        &mut store,
+        include_str!("../../../test-data/input/unicode.json"),
+    )?;
+    test_round_trip(
+        &mut store,
+        include_str!("../../../test-data/input/values.json"),
+    )?;
+    test_round_trip(
+        &mut store,
+        include_str!("../../../test-data/input/weird.json"),
+    )?;
+    test_round_trip(
+        &mut store,
+        include_str!("../../../test-data/fuzzies/1.json"),
+    )?;
+    test_round_trip(
+        &mut store,
+        r#"{"a b":[2,{"\u0000":"\"","\u20ac":3}],"a":1,"\ud83d\ude00":0,"\ufb33":0}"#,
+    )?;
+    test_round_trip(&mut store, "null")?;
+    test_round_trip(&mut store, "\"hello\"")?;
+    test_round_trip(&mut store, "[]")?;
+    test_round_trip(&mut store, "{}")?;
+    Ok(())
+}
+
+#[test]
+fn test_deduplication() -> io::Result<()> {
+    let mut store = Store::new(MemoryBackend::new());
+    let shared = json!({ "host": "localhost", "ports": [80, 443] });
+    let config = json!({
+        "a": shared,
+        "b": shared,
+        "c": [shared, shared],
+    });
+    let hash = store.put(&config)?;
+
+    // the root, `c`, `shared`, and `shared.ports`
+    assert_eq!(store.backend().len(), 4);
+    assert!(store.contains(&to_hash(&shared)?)?);
+    assert!(store.contains(&to_hash(&shared["ports"])?)?);
+
+    // the same subtrees, in a different key order
+    let reordered: Value = from_str(r#"{"ports":[80,443],"host":"localhost"}"#)?;
+    store.put(&reordered)?;
+    assert_eq!(store.backend().len(), 4);
+
+    assert_eq!(store.get(&hash)?, config);
+    Ok(())
+}
+
+#[test]
+fn test_missing() -> io::Result<()> {
+    let store = Store::new(MemoryBackend::new());
+    let hash = to_hash(&json!({ "a": 1 }))?;
+    assert!(!store.contains(&hash)?);
+    assert_eq!(
+        store.get(&hash).unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+    Ok(())
+}
+
+#[test]
+fn test_fs_round_trip() -> io::Result<()> {
+    let root = temp_dir().join(format!("json-canon-store-{}", process::id()));
+    let result = (|| {
+        let mut store = Store::new(FsBackend::new(&root)?);
+        test_round_trip(
+            &mut store,
+            include_str!("../../../test-data/input/structures.json"),
+        )?;
+        test_round_trip(
+            &mut store,
+            include_str!("../../../test-data/input/weird.json"),
+        )?;
+
+        let value = json!({ "b": [1, 2, { "c": null }], "a": "x" });
+        let hash = Store::new(FsBackend::new(&root)?).put(&value)?;
+        let reopened = Store::new(FsBackend::new(&root)?);
+        assert_eq!(reopened.get(&hash)?, value);
+        assert_eq!(reopened.to_vec(&hash)?, to_vec(&value)?);
+        Ok(())
+    })();
+    fs::remove_dir_all(&root)?;
+    result
+}
+
+#[test]
+fn test_fs_tampered() -> io::Result<()> {
+    let root = temp_dir().join(format!("json-canon-store-tampered-{}", process::id()));
+    let result = (|| {
+        let mut store = Store::new(FsBackend::new(&root)?);
+        let inner = json!({ "amount": 500 });
+        let hash = store.put(&json!({ "a": inner, "b": [1, 2] }))?;
+
+        let hex = to_hex(&to_hash(&inner)?);
+        fs::write(
+            root.join(&hex[..2]).join(&hex[2..]),
+            r#"{"object":[["amount",900]]}"#,
+        )?;
+
+        assert_eq!(
+            store.get(&hash).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        let mut output = Vec::new();
+        assert_eq!(
+            store.write_to(&hash, &mut output).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert!(output.is_empty());
+        assert_eq!(
+            store.to_vec(&to_hash(&inner)?).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        Ok(())
+    })();
+    fs::remove_dir_all(&root)?;
+    result
+}
+
+#[test]
+fn test_fs_concurrent_puts() -> io::Result<()> {
+    let root = temp_dir().join(format!("json-canon-store-concurrent-{}", process::id()));
+    let result = (|| {
+        let backend = FsBackend::new(&root)?;
+        let value = json!({ "a": [1, 2, { "b": null }], "c": "d" });
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let backend = backend.clone();
+                let value = value.clone();
+                thread::spawn(move || Store::new(backend).put(&value))
+            })
+            .collect();
+        for thread in threads {
+            let hash = thread.join().expect("thread panicked")?;
+            assert_eq!(Store::new(backend.clone()).get(&hash)?, value);
+        }
+        Ok(())
+    })();
+    fs::remove_dir_all(&root)?;
+    result
+}
@@ -0,0 +1,96 @@
+use std::io::{self, ErrorKind};
+
+use json_canon::stream::{canonicalize_stream, Framing, OnError, Records, StreamOptions, RS};
+
+#[track_caller]
+fn test_stream(input: &[u8], options: StreamOptions, expected: &[u8]) -> io::Result<Vec<usize>> {
+    let mut output = Vec::new();
+    let report = canonicalize_stream(input, &mut output, options)?;
+    assert_eq!(
+        String::from_utf8_lossy(&output),
+        String::from_utf8_lossy(expected)
+    );
+    Ok(report.errors.iter().map(|error| error.index).collect())
+}
+
+#[test]
+fn test_lines() -> io::Result<()> {
+    let input = b"{\"b\": 1, \"a\": 2.0}\n\n  [1e2]  \r\n\"x\"";
+    let errors = test_stream(
+        input,
+        StreamOptions::default(),
+        b"{\"a\":2,\"b\":1}\n[100]\n\"x\"\n",
+    )?;
+    assert!(errors.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_sequence() -> io::Result<()> {
+    let input = b"\x1e{\"b\": 1, \"a\": 2}\n\x1e\n\x1etrue\n\x1e[\n  null\n]\n";
+    let options = StreamOptions {
+        input: Framing::Sequence,
+        output: Framing::Sequence,
+        ..StreamOptions::default()
+    };
+    let errors = test_stream(
+        input,
+        options,
+        b"\x1e{\"a\":2,\"b\":1}\n\x1etrue\n\x1e[null]\n",
+    )?;
+    assert!(errors.is_empty());
+
+    let options = StreamOptions {
+        input: Framing::Sequence,
+        ..StreamOptions::default()
+    };
+    test_stream(input, options, b"{\"a\":2,\"b\":1}\ntrue\n[null]\n")?;
+    Ok(())
+}
+
+#[test]
+fn test_skip_errors() -> io::Result<()> {
+    let input = b"1\n{\"a\":\n2\n18446744073709551615\n[\n3\n";
+    let errors = test_stream(input, StreamOptions::default(), b"1\n2\n3\n")?;
+    assert_eq!(errors, vec![1, 3, 4]);
+    Ok(())
+}
+
+#[test]
+fn test_abort_on_error() {
+    let options = StreamOptions {
+        on_error: OnError::Abort,
+        ..StreamOptions::default()
+    };
+    let mut output = Vec::new();
+    let error = canonicalize_stream(&b"1\n\n{\n2\n"[..], &mut output, options).unwrap_err();
+    assert!(error.to_string().starts_with("Record 1: "), "{}", error);
+    assert_eq!(output, b"1\n");
+}
+
+#[test]
+fn test_max_record_len() -> io::Result<()> {
+    let input = b"[1, 2, 3]\n[1, 2, 3, 4, 5, 6, 7, 8, 9]\n[4,5]\n";
+    let options = StreamOptions {
+        max_record_len: Some(9),
+        ..StreamOptions::default()
+    };
+    let errors = test_stream(input, options, b"[1,2,3]\n[4,5]\n")?;
+    assert_eq!(errors, vec![1]);
+    Ok(())
+}
+
+#[test]
+fn test_records() -> io::Result<()> {
+    let input = [&[RS][..], b"1\n", &[RS], b"x\n", &[RS], b"{}"].concat();
+    let records: Vec<_> = Records::new(&input[..], Framing::Sequence).collect::<io::Result<_>>()?;
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0].as_ref().unwrap(), &serde_json::json!(1));
+    let error = records[1].as_ref().unwrap_err();
+    assert_eq!(
+        (error.index, error.error.kind()),
+        (1, ErrorKind::InvalidData)
+    );
+    assert_eq!(records[2].as_ref().unwrap(), &serde_json::json!({}));
+    Ok(())
+}
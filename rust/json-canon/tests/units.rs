@@ -1,6 +1,6 @@
 use std::{collections::BTreeMap, fmt::Debug, io};
 
-use json_canon::to_string;
+use json_canon::{to_string, to_string_pretty};
 use serde::Serialize;
 use serde_json::{from_str, json, Value};
 
@@ -351,6 +351,16 @@ fn test_object_with_large_integer_keys() -> io::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_object_with_integer_keys_sorted_as_strings() -> io::Result<()> {
+    let expected = r#"{"10":"Ten","9":"Nine"}"#;
+    let input_rs_u64: BTreeMap<u64, &str> = treemap![9 => "Nine", 10 => "Ten"];
+    let input_rs_i128: BTreeMap<i128, &str> = treemap![9 => "Nine", 10 => "Ten"];
+    test_ok(expected, input_rs_u64)?;
+    test_ok(expected, input_rs_i128)?;
+    Ok(())
+}
+
 #[test]
 fn test_object_with_unit_variant_keys() -> io::Result<()> {
     let expected = r#"{"One":"One","Three":"Three","Two":"Two"}"#;
@@ -500,3 +510,50 @@ fn test_bug_utf8_sort() -> io::Result<()> {
     assert_eq!(actual, expected);
     Ok(())
 }
+
+#[test]
+fn test_object_with_duplicate_keys() -> io::Result<()> {
+    struct Duplicates;
+
+    impl Serialize for Duplicates {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            use serde::ser::SerializeMap;
+
+            let mut map = serializer.serialize_map(Some(3))?;
+            map.serialize_entry("b", &1)?;
+            map.serialize_entry("a", &2)?;
+            map.serialize_entry("b", &3)?;
+            map.end()
+        }
+    }
+
+    test_ok(r#"{"a":2,"b":1,"b":3}"#, Duplicates)
+}
+
+#[test]
+fn test_pretty() -> io::Result<()> {
+    let input = json!({
+        "b": [1.0, {}, [], { "d": null, "c": "\u{20ac}" }],
+        "a": { "f": 1e21, "e": true },
+    });
+    let expected = r#"{
+  "a": {
+    "e": true,
+    "f": 1e+21
+  },
+  "b": [
+    1,
+    {},
+    [],
+    {
+      "c": "€",
+      "d": null
+    }
+  ]
+}"#;
+    assert_eq!(to_string_pretty(&input)?, expected);
+    Ok(())
+}